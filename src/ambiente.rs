@@ -0,0 +1,360 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::rc::{Rc, Weak};
+
+use crate::nativas;
+use crate::valor::{Classe, Funcao, Instancia, Value};
+
+// Escopo de variáveis. Cada bloco cria um ambiente novo que aponta para o
+// ambiente onde ele foi criado; a busca por nomes segue essa cadeia até o
+// escopo global.
+#[derive(Debug, Default)]
+pub struct Ambiente {
+    valores: HashMap<String, Value>,
+    pai: Option<Rc<RefCell<Ambiente>>>,
+    // Já registrado em `Contexto::capturados`
+    capturado: bool,
+}
+
+impl Ambiente {
+    pub fn new() -> Self {
+        Ambiente::default()
+    }
+
+    pub fn com_pai(pai: Rc<RefCell<Ambiente>>) -> Self {
+        Ambiente { valores: HashMap::new(), pai: Some(pai), capturado: false }
+    }
+
+    // Declara (ou redeclara) a variável neste escopo
+    pub fn definir(&mut self, nome: &str, valor: Value) {
+        self.valores.insert(nome.to_string(), valor);
+    }
+
+    pub fn obter(&self, nome: &str) -> Option<Value> {
+        match self.valores.get(nome) {
+            Some(valor) => Some(valor.clone()),
+            None => self.pai.as_ref()?.borrow().obter(nome),
+        }
+    }
+
+    // Altera a variável no escopo mais interno em que ela existe. Retorna
+    // false se ela não foi declarada em nenhum escopo.
+    pub fn atribuir(&mut self, nome: &str, valor: Value) -> bool {
+        if let Some(atual) = self.valores.get_mut(nome) {
+            *atual = valor;
+            return true;
+        }
+        match &self.pai {
+            Some(pai) => pai.borrow_mut().atribuir(nome, valor),
+            None => false,
+        }
+    }
+
+    pub fn nomes(&self) -> Vec<String> {
+        let mut nomes: Vec<_> = self.valores.keys().cloned().collect();
+        nomes.sort();
+        nomes
+    }
+}
+
+// Valor padrão de `Contexto::profundidade_maxima`
+pub const PROFUNDIDADE_MAXIMA_PADRAO: usize = 400;
+
+// Quantidade de ambientes capturados e instâncias a partir da qual ciclos
+// inalcançáveis começam a ser procurados
+const COLETA_MINIMA: usize = 1024;
+
+// Estado da execução: o escopo atual e as opções do interpretador
+pub struct Contexto {
+    pub globais: Rc<RefCell<Ambiente>>,
+    pub ambiente: Rc<RefCell<Ambiente>>,
+    pub profundidade: usize,
+    // Limite de níveis aninhados de avaliação (expressões, declarações e
+    // chamadas), para que uma recursão infinita vire um erro em vez de
+    // estourar a pilha do próprio interpretador. O padrão cabe numa thread
+    // de 2 MB mesmo em builds de debug
+    pub profundidade_maxima: usize,
+    // Modo de compatibilidade: blocos não criam escopo e as variáveis
+    // declaradas dentro deles continuam visíveis depois do bloco
+    pub blocos_compartilham_escopo: bool,
+    // Mostra os símbolos, a árvore sintática e o resultado de cada declaração
+    // em `depuracao` durante a execução
+    pub rastreamento: bool,
+    // Para onde vão a saída do `print`, as informações de depuração (símbolos,
+    // árvore sintática, resultados) e as mensagens de erro
+    pub saida: Box<dyn Write>,
+    pub depuracao: Box<dyn Write>,
+    pub diagnosticos: Box<dyn Write>,
+    // Ambientes guardados como closure por alguma função. Uma função guardada
+    // no próprio ambiente que ela captura (como toda função global) forma um
+    // ciclo de Rc, então esses ambientes são esvaziados quando o contexto é
+    // descartado ou reiniciado, para que a memória seja liberada. Durante a
+    // execução, os ciclos que o programa não alcança mais são esvaziados
+    // sempre que os dois vetores juntos chegam a `proxima_coleta`.
+    capturados: Vec<Weak<RefCell<Ambiente>>>,
+    // Instâncias criadas pelo programa, que também formam ciclos quando um
+    // campo guarda a própria instância (`a.eu = a`) ou outra que aponta de
+    // volta para ela
+    instancias: Vec<Weak<RefCell<Instancia>>>,
+    proxima_coleta: usize,
+}
+
+impl Contexto {
+    pub fn new() -> Self {
+        let mut ambiente_global = Ambiente::new();
+        nativas::registrar(&mut ambiente_global);
+        let globais = Rc::new(RefCell::new(ambiente_global));
+        Contexto {
+            ambiente: Rc::clone(&globais),
+            globais,
+            profundidade: 0,
+            profundidade_maxima: PROFUNDIDADE_MAXIMA_PADRAO,
+            blocos_compartilham_escopo: false,
+            rastreamento: false,
+            saida: Box::new(io::stdout()),
+            depuracao: Box::new(io::stdout()),
+            diagnosticos: Box::new(io::stderr()),
+            capturados: Vec::new(),
+            instancias: Vec::new(),
+            proxima_coleta: COLETA_MINIMA,
+        }
+    }
+
+    // Descarta todas as variáveis (mantendo as funções nativas), mas preserva
+    // as opções e os destinos de saída
+    pub fn reiniciar(&mut self) {
+        self.liberar_ciclos();
+        let mut ambiente_global = Ambiente::new();
+        nativas::registrar(&mut ambiente_global);
+        self.globais = Rc::new(RefCell::new(ambiente_global));
+        self.ambiente = Rc::clone(&self.globais);
+        self.profundidade = 0;
+    }
+
+    // Registra `ambiente` como closure de uma função e o devolve
+    pub fn capturar(&mut self, ambiente: Rc<RefCell<Ambiente>>) -> Rc<RefCell<Ambiente>> {
+        if !std::mem::replace(&mut ambiente.borrow_mut().capturado, true) {
+            self.antes_de_registrar();
+            self.capturados.push(Rc::downgrade(&ambiente));
+        }
+        ambiente
+    }
+
+    // Cria uma instância de `classe`, registrada para que ciclos entre
+    // instâncias possam ser liberados
+    pub fn nova_instancia(&mut self, classe: Rc<Classe>) -> Rc<RefCell<Instancia>> {
+        self.antes_de_registrar();
+        let instancia = Rc::new(RefCell::new(Instancia::new(classe)));
+        self.instancias.push(Rc::downgrade(&instancia));
+        instancia
+    }
+
+    fn antes_de_registrar(&mut self) {
+        if self.capturados.len() + self.instancias.len() >= self.proxima_coleta {
+            self.coletar_ciclos();
+        }
+    }
+
+    // Esvazia os ambientes e instâncias que só são alcançáveis a partir de
+    // ciclos, e não pelo programa. A próxima coleta acontece quando o número
+    // de objetos registrados dobrar.
+    fn coletar_ciclos(&mut self) {
+        for objeto in inalcancaveis(self.registrados()) {
+            objeto.esvaziar();
+        }
+        self.capturados.retain(|fraco| fraco.strong_count() > 0);
+        self.instancias.retain(|fraco| fraco.strong_count() > 0);
+        self.proxima_coleta = ((self.capturados.len() + self.instancias.len()) * 2).max(COLETA_MINIMA);
+    }
+
+    fn liberar_ciclos(&mut self) {
+        for objeto in self.registrados() {
+            objeto.esvaziar();
+        }
+        self.capturados.clear();
+        self.instancias.clear();
+    }
+
+    fn registrados(&self) -> Vec<Objeto> {
+        let ambientes = self.capturados.iter().filter_map(Weak::upgrade).map(Objeto::Ambiente);
+        let instancias = self.instancias.iter().filter_map(Weak::upgrade).map(Objeto::Instancia);
+        ambientes.chain(instancias).collect()
+    }
+
+    pub fn definir(&mut self, nome: &str, valor: Value) {
+        self.ambiente.borrow_mut().definir(nome, valor);
+    }
+
+    pub fn obter(&self, nome: &str) -> Option<Value> {
+        self.ambiente.borrow().obter(nome)
+    }
+
+    pub fn atribuir(&mut self, nome: &str, valor: Value) -> bool {
+        self.ambiente.borrow_mut().atribuir(nome, valor)
+    }
+
+    // Executa `f` num escopo filho do atual, restaurando o escopo anterior
+    // no final (mesmo em caso de erro)
+    pub fn em_novo_escopo<T>(&mut self, f: impl FnOnce(&mut Contexto) -> T) -> T {
+        let novo = Ambiente::com_pai(Rc::clone(&self.ambiente));
+        self.no_ambiente(novo, f)
+    }
+
+    // Executa `f` usando `ambiente` como escopo atual
+    pub fn no_ambiente<T>(&mut self, ambiente: Ambiente, f: impl FnOnce(&mut Contexto) -> T) -> T {
+        let anterior = std::mem::replace(&mut self.ambiente, Rc::new(RefCell::new(ambiente)));
+        let resultado = f(self);
+        self.ambiente = anterior;
+        resultado
+    }
+}
+
+impl Drop for Contexto {
+    fn drop(&mut self) {
+        self.liberar_ciclos();
+    }
+}
+
+impl Default for Contexto {
+    fn default() -> Self {
+        Contexto::new()
+    }
+}
+
+// Objeto compartilhado por Rc que pode fazer parte de um ciclo
+enum Objeto {
+    Ambiente(Rc<RefCell<Ambiente>>),
+    Funcao(Rc<Funcao>),
+    Classe(Rc<Classe>),
+    Instancia(Rc<RefCell<Instancia>>),
+}
+
+impl Objeto {
+    fn de_valor(valor: &Value) -> Option<Objeto> {
+        match valor {
+            Value::Funcao(funcao) => Some(Objeto::Funcao(Rc::clone(funcao))),
+            Value::Classe(classe) => Some(Objeto::Classe(Rc::clone(classe))),
+            Value::Instancia(instancia) => Some(Objeto::Instancia(Rc::clone(instancia))),
+            _ => None,
+        }
+    }
+
+    fn endereco(&self) -> usize {
+        match self {
+            Objeto::Ambiente(rc) => Rc::as_ptr(rc) as *const () as usize,
+            Objeto::Funcao(rc) => Rc::as_ptr(rc) as *const () as usize,
+            Objeto::Classe(rc) => Rc::as_ptr(rc) as *const () as usize,
+            Objeto::Instancia(rc) => Rc::as_ptr(rc) as *const () as usize,
+        }
+    }
+
+    fn referencias(&self) -> usize {
+        match self {
+            Objeto::Ambiente(rc) => Rc::strong_count(rc),
+            Objeto::Funcao(rc) => Rc::strong_count(rc),
+            Objeto::Classe(rc) => Rc::strong_count(rc),
+            Objeto::Instancia(rc) => Rc::strong_count(rc),
+        }
+    }
+
+    // Descarta as variáveis do ambiente ou os campos da instância. Os valores
+    // são descartados fora do borrow_mut, já que podem ser os últimos donos
+    // de outros objetos.
+    fn esvaziar(&self) {
+        match self {
+            Objeto::Ambiente(ambiente) => {
+                if let Ok(mut ambiente) = ambiente.try_borrow_mut() {
+                    let valores = std::mem::take(&mut ambiente.valores);
+                    drop(ambiente);
+                    drop(valores);
+                }
+            }
+            Objeto::Instancia(instancia) => {
+                if let Ok(mut instancia) = instancia.try_borrow_mut() {
+                    let campos = std::mem::take(&mut instancia.campos);
+                    drop(instancia);
+                    drop(campos);
+                }
+            }
+            Objeto::Funcao(_) | Objeto::Classe(_) => {}
+        }
+    }
+
+    // Os objetos referenciados diretamente por este. Um objeto emprestado
+    // para escrita não é inspecionado; os que ele referencia passam por
+    // alcançáveis, o que só adia a coleta deles.
+    fn filhos(&self) -> Vec<Objeto> {
+        let mut filhos = Vec::new();
+        match self {
+            Objeto::Ambiente(ambiente) => {
+                if let Ok(ambiente) = ambiente.try_borrow() {
+                    filhos.extend(ambiente.pai.iter().map(|pai| Objeto::Ambiente(Rc::clone(pai))));
+                    filhos.extend(ambiente.valores.values().filter_map(Objeto::de_valor));
+                }
+            }
+            Objeto::Funcao(funcao) => filhos.push(Objeto::Ambiente(Rc::clone(&funcao.closure))),
+            Objeto::Classe(classe) => {
+                filhos.extend(classe.superclasse.iter().map(|superclasse| Objeto::Classe(Rc::clone(superclasse))));
+                filhos.extend(classe.metodos.values().map(|metodo| Objeto::Funcao(Rc::clone(metodo))));
+            }
+            Objeto::Instancia(instancia) => {
+                if let Ok(instancia) = instancia.try_borrow() {
+                    filhos.push(Objeto::Classe(Rc::clone(&instancia.classe)));
+                    filhos.extend(instancia.campos.values().filter_map(Objeto::de_valor));
+                }
+            }
+        }
+        filhos
+    }
+}
+
+// Encontra, entre os objetos alcançáveis a partir de `registrados`, os que
+// só são referenciados por outros objetos inalcançáveis.
+// Quem segura esses objetos de fora (as variáveis do contexto, a pilha do
+// interpretador, o programa que embute o interpretador) não é visível aqui,
+// mas aparece no contador de referências: um objeto com mais referências do
+// que as vindas de dentro do grafo é alcançável, assim como tudo que ele
+// referencia.
+fn inalcancaveis(registrados: Vec<Objeto>) -> Vec<Objeto> {
+    // Cada objeto, com o número de referências a ele vindas do próprio grafo
+    let mut grafo: HashMap<usize, (Objeto, usize)> = HashMap::new();
+    let mut pendentes = Vec::new();
+    for objeto in registrados {
+        pendentes.push(objeto.endereco());
+        grafo.insert(objeto.endereco(), (objeto, 0));
+    }
+    while let Some(endereco) = pendentes.pop() {
+        for filho in grafo[&endereco].0.filhos() {
+            let endereco_filho = filho.endereco();
+            match grafo.get_mut(&endereco_filho) {
+                Some((_, internas)) => *internas += 1,
+                None => {
+                    pendentes.push(endereco_filho);
+                    grafo.insert(endereco_filho, (filho, 1));
+                }
+            }
+        }
+    }
+
+    // A cópia guardada no grafo também conta como uma referência
+    let mut alcancaveis: HashSet<usize> = grafo
+        .iter()
+        .filter(|(_, (objeto, internas))| objeto.referencias() > internas + 1)
+        .map(|(endereco, _)| *endereco)
+        .collect();
+    let mut pendentes: Vec<usize> = alcancaveis.iter().copied().collect();
+    while let Some(endereco) = pendentes.pop() {
+        for filho in grafo[&endereco].0.filhos() {
+            if alcancaveis.insert(filho.endereco()) {
+                pendentes.push(filho.endereco());
+            }
+        }
+    }
+
+    grafo
+        .into_iter()
+        .filter(|(endereco, _)| !alcancaveis.contains(endereco))
+        .map(|(_, (objeto, _))| objeto)
+        .collect()
+}
@@ -1,9 +1,15 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::diagnostico;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Simbolo {
     NumeroInteiro(i64),
     NumeroFloat(f64),
     String(String),
+    StringInterpolada(Vec<ParteString>), // "texto ${expressão} texto"
     Identificador(String),
     Ponto,
     AbreParenteses,
@@ -27,52 +33,240 @@ pub enum Simbolo {
     MenorIgual,        // <=
     IgualIgual,        // ==
     Diferente,         // !=
+    Seta,              // =>
     Var,               // var
     If,                // if
     Else,              // else
     While,             // while
     Print,             // print
+    Fun,               // fun
+    Return,            // return
+    Class,             // class
+    This,              // this
+    Super,             // super
+    True,              // true
+    False,             // false
+    Nil,               // nil
+    Comentario(String), // // ou /* */ (só em analisar_com_comentarios)
     Fim,
 }
 
-pub fn analisar(texto: &str) -> Vec<Simbolo> {
+// Pedaço de uma string interpolada: texto literal ou os símbolos da
+// expressão entre `${` e `}`, terminados por `Simbolo::Fim`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParteString {
+    Texto(String),
+    Codigo(Vec<Token>),
+}
+
+// Descrição do símbolo usada nas mensagens de erro
+impl fmt::Display for Simbolo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let texto = match self {
+            // Literais nunca são negativos; i64::MIN representa 2^63 (veja `ler_numero`)
+            Simbolo::NumeroInteiro(n) => return write!(f, "número {}", n.unsigned_abs()),
+            Simbolo::NumeroFloat(n) => return write!(f, "número {}", n),
+            Simbolo::String(s) => return write!(f, "string {:?}", s),
+            Simbolo::StringInterpolada(_) => "string interpolada",
+            Simbolo::Identificador(nome) => return write!(f, "identificador '{}'", nome),
+            Simbolo::Ponto => "'.'",
+            Simbolo::AbreParenteses => "'('",
+            Simbolo::FechaParenteses => "')'",
+            Simbolo::AbreChaves => "'{'",
+            Simbolo::FechaChaves => "'}'",
+            Simbolo::Virgula => "','",
+            Simbolo::PontoVirgula => "';'",
+            Simbolo::Negacao => "'!'",
+            Simbolo::Soma => "'+'",
+            Simbolo::Subtracao => "'-'",
+            Simbolo::Multiplicacao => "'*'",
+            Simbolo::Divisao => "'/'",
+            Simbolo::Modulo => "'%'",
+            Simbolo::And => "'and'",
+            Simbolo::Or => "'or'",
+            Simbolo::Igual => "'='",
+            Simbolo::Maior => "'>'",
+            Simbolo::Menor => "'<'",
+            Simbolo::MaiorIgual => "'>='",
+            Simbolo::MenorIgual => "'<='",
+            Simbolo::IgualIgual => "'=='",
+            Simbolo::Diferente => "'!='",
+            Simbolo::Seta => "'=>'",
+            Simbolo::Var => "'var'",
+            Simbolo::If => "'if'",
+            Simbolo::Else => "'else'",
+            Simbolo::While => "'while'",
+            Simbolo::Print => "'print'",
+            Simbolo::Fun => "'fun'",
+            Simbolo::Return => "'return'",
+            Simbolo::Class => "'class'",
+            Simbolo::This => "'this'",
+            Simbolo::Super => "'super'",
+            Simbolo::True => "'true'",
+            Simbolo::False => "'false'",
+            Simbolo::Nil => "'nil'",
+            Simbolo::Comentario(_) => "comentário",
+            Simbolo::Fim => "fim da entrada",
+        };
+        write!(f, "{}", texto)
+    }
+}
+
+// Posição de um trecho do código fonte: deslocamento em bytes, linha e coluna
+// (ambas começando em 1) e tamanho em bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub inicio: usize,
+    pub linha: usize,
+    pub coluna: usize,
+    pub tamanho: usize,
+}
+
+impl Span {
+    // Span que vai do início de `self` até o fim de `outro`
+    pub fn unir(&self, outro: &Span) -> Span {
+        let fim = (outro.inicio + outro.tamanho).max(self.inicio + self.tamanho);
+        Span { tamanho: fim - self.inicio, ..*self }
+    }
+
+    pub fn fim(&self) -> usize {
+        self.inicio + self.tamanho
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub simbolo: Simbolo,
+    pub span: Span,
+}
+
+// Percorre os caracteres do texto acompanhando a posição atual
+#[derive(Clone)]
+struct Leitor<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    base: usize,
+    posicao: usize,
+    linha: usize,
+    coluna: usize,
+}
+
+impl<'a> Leitor<'a> {
+    fn new(texto: &'a str) -> Self {
+        Leitor::em(texto, Span { inicio: 0, linha: 1, coluna: 1, tamanho: 0 })
+    }
+
+    // Leitor de um trecho que começa na posição `marca` de um texto maior,
+    // para que os spans continuem relativos ao texto completo
+    fn em(trecho: &'a str, marca: Span) -> Self {
+        Leitor {
+            chars: trecho.char_indices().peekable(),
+            base: marca.inicio,
+            posicao: marca.inicio,
+            linha: marca.linha,
+            coluna: marca.coluna,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek().map(|(_, c)| c)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let (i, c) = self.chars.next()?;
+        self.posicao = self.base + i + c.len_utf8();
+        if c == '\n' {
+            self.linha += 1;
+            self.coluna = 1;
+        } else {
+            self.coluna += 1;
+        }
+        Some(c)
+    }
+
+    // Span vazio na posição atual, usado para marcar o início de um símbolo
+    fn marca(&self) -> Span {
+        Span { inicio: self.posicao, linha: self.linha, coluna: self.coluna, tamanho: 0 }
+    }
+
+    fn span_desde(&self, marca: Span) -> Span {
+        Span { tamanho: self.posicao - marca.inicio, ..marca }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoLexError {
+    CaractereInvalido(char),
+    StringNaoTerminada,
+    InterpolacaoNaoTerminada,
+    ComentarioNaoTerminado,
+    InteiroMuitoGrande(String),
+    NumeroInvalido { numero: String, motivo: &'static str },
+}
+
+// Erro léxico: o analisador registra o problema e continua a leitura
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub tipo: TipoLexError,
+    pub span: Span,
+}
+
+impl LexError {
+    pub fn renderizar(&self, fonte: &str) -> String {
+        diagnostico::renderizar(fonte, self.span, &self.to_string())
+    }
+}
+
+impl fmt::Display for TipoLexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TipoLexError::CaractereInvalido(c) => write!(f, "caractere inválido {:?}", c),
+            TipoLexError::StringNaoTerminada => write!(f, "string não terminada"),
+            TipoLexError::InterpolacaoNaoTerminada => write!(f, "'${{' sem o '}}' correspondente"),
+            TipoLexError::ComentarioNaoTerminado => write!(f, "comentário de bloco não terminado"),
+            TipoLexError::InteiroMuitoGrande(numero) => {
+                write!(f, "o inteiro {} não cabe em 64 bits", numero)
+            }
+            TipoLexError::NumeroInvalido { numero, motivo } => {
+                write!(f, "número inválido '{}': {}", numero, motivo)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tipo)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+// Retorna os símbolos encontrados junto com os erros léxicos. Caracteres
+// inválidos são ignorados para que a análise possa continuar.
+pub fn analisar(texto: &str) -> (Vec<Token>, Vec<LexError>) {
+    analisar_texto(texto, false)
+}
+
+// Como `analisar`, mas mantém os comentários como símbolos
+// `Simbolo::Comentario`, para ferramentas como formatadores
+pub fn analisar_com_comentarios(texto: &str) -> (Vec<Token>, Vec<LexError>) {
+    analisar_texto(texto, true)
+}
+
+fn analisar_texto(texto: &str, manter_comentarios: bool) -> (Vec<Token>, Vec<LexError>) {
+    analisar_trecho(texto, Leitor::new(texto), manter_comentarios)
+}
+
+// `texto` é sempre o código completo; `chars` pode percorrer só uma parte
+// dele, como a expressão de uma interpolação
+fn analisar_trecho(texto: &str, mut chars: Leitor, manter_comentarios: bool) -> (Vec<Token>, Vec<LexError>) {
     let mut simbolos = Vec::new();
-    let mut chars = texto.chars().peekable();
+    let mut erros = Vec::new();
 
     while let Some(&c) = chars.peek() {
-        match c {
-            '"' => {
-                chars.next(); // consome a aspa inicial
-                let mut string = String::new();
-                while let Some(&d) = chars.peek() {
-                    match d {
-                        '"' => {
-                            chars.next(); // consome a aspa final
-                            break;
-                        }
-                        '\\' => {
-                            chars.next(); // consome barra
-                            if let Some(&esc) = chars.peek() {
-                                let escaped = match esc {
-                                    '"' => '"',
-                                    '\\' => '\\',
-                                    'n' => '\n',
-                                    'r' => '\r',
-                                    't' => '\t',
-                                    _ => esc,
-                                };
-                                string.push(escaped);
-                                chars.next();
-                            }
-                        }
-                        _ => {
-                            string.push(d);
-                            chars.next();
-                        }
-                    }
-                }
-                simbolos.push(Simbolo::String(string));
-            }
+        let inicio = chars.marca();
+        let simbolo = match c {
+            '"' => ler_string(texto, &mut chars, inicio, false, &mut erros),
             'a'..='z' | 'A'..='Z' | '_' => {
                 let mut identificador = String::new();
                 while let Some(&d) = chars.peek() {
@@ -84,95 +278,137 @@ pub fn analisar(texto: &str) -> Vec<Simbolo> {
                     }
                 }
                 match identificador.as_str() {
-                    "and" => simbolos.push(Simbolo::And),
-                    "or" => simbolos.push(Simbolo::Or),
-                    "var" => simbolos.push(Simbolo::Var),
-                    "if" => simbolos.push(Simbolo::If),
-                    "else" => simbolos.push(Simbolo::Else),
-                    "while" => simbolos.push(Simbolo::While),
-                    "print" => simbolos.push(Simbolo::Print),
-                    _ => simbolos.push(Simbolo::Identificador(identificador)),
+                    // r"..." é uma string bruta
+                    "r" if chars.peek() == Some(&'"') => ler_string(texto, &mut chars, inicio, true, &mut erros),
+                    "and" => Simbolo::And,
+                    "or" => Simbolo::Or,
+                    "var" => Simbolo::Var,
+                    "if" => Simbolo::If,
+                    "else" => Simbolo::Else,
+                    "while" => Simbolo::While,
+                    "print" => Simbolo::Print,
+                    "fun" => Simbolo::Fun,
+                    "return" => Simbolo::Return,
+                    "class" => Simbolo::Class,
+                    "this" => Simbolo::This,
+                    "super" => Simbolo::Super,
+                    "true" => Simbolo::True,
+                    "false" => Simbolo::False,
+                    "nil" => Simbolo::Nil,
+                    _ => Simbolo::Identificador(identificador),
                 }
             }
             '.' => {
                 chars.next();
-                simbolos.push(Simbolo::Ponto);
-            }
-            '0'..='9' => {
-                let mut numero_str = String::new();
-                let mut is_float = false;
-
-                while let Some(&d) = chars.peek() {
-                    if d.is_ascii_digit() {
-                        numero_str.push(d);
-                        chars.next();
-                    } else if d == '.' && !is_float {
-                        is_float = true;
-                        numero_str.push(d);
-                        chars.next();
-                    } else {
-                        break;
-                    }
-                }
-
-                if is_float {
-                    simbolos.push(Simbolo::NumeroFloat(numero_str.parse().unwrap()));
-                } else {
-                    simbolos.push(Simbolo::NumeroInteiro(numero_str.parse().unwrap()));
+                // `.5` não é aceito: números começam sempre com um dígito
+                if chars.peek().is_some_and(char::is_ascii_digit) {
+                    let marca = chars.marca();
+                    ler_numero(texto, &mut chars, marca, &mut Vec::new());
+                    let numero = texto[inicio.inicio..chars.posicao].to_string();
+                    let tipo = TipoLexError::NumeroInvalido { numero, motivo: "faltam dígitos antes do '.'" };
+                    erros.push(LexError { tipo, span: chars.span_desde(inicio) });
+                    continue;
                 }
+                Simbolo::Ponto
             }
+            '0'..='9' => match ler_numero(texto, &mut chars, inicio, &mut erros) {
+                Some(simbolo) => simbolo,
+                None => continue,
+            },
             '+' => {
                 chars.next();
-                simbolos.push(Simbolo::Soma);
+                Simbolo::Soma
             }
             '-' => {
                 chars.next();
-                simbolos.push(Simbolo::Subtracao);
+                Simbolo::Subtracao
             }
             '*' => {
                 chars.next();
-                simbolos.push(Simbolo::Multiplicacao);
+                Simbolo::Multiplicacao
             }
             '/' => {
                 chars.next();
-                simbolos.push(Simbolo::Divisao);
+                match chars.peek() {
+                    // Comentário de linha: vai até o fim da linha
+                    Some(&'/') => {
+                        while let Some(&d) = chars.peek() {
+                            if d == '\n' {
+                                break;
+                            }
+                            chars.next();
+                        }
+                        Simbolo::Comentario(texto[inicio.inicio..chars.posicao].to_string())
+                    }
+                    // Comentário de bloco, que pode conter outros comentários de bloco
+                    Some(&'*') => {
+                        chars.next();
+                        let mut profundidade = 1;
+                        while profundidade > 0 {
+                            match chars.next() {
+                                Some('/') if chars.peek() == Some(&'*') => {
+                                    chars.next();
+                                    profundidade += 1;
+                                }
+                                Some('*') if chars.peek() == Some(&'/') => {
+                                    chars.next();
+                                    profundidade -= 1;
+                                }
+                                Some(_) => {}
+                                None => {
+                                    let span = chars.span_desde(inicio);
+                                    let tipo = TipoLexError::ComentarioNaoTerminado;
+                                    erros.push(LexError { tipo, span });
+                                    break;
+                                }
+                            }
+                        }
+                        Simbolo::Comentario(texto[inicio.inicio..chars.posicao].to_string())
+                    }
+                    _ => Simbolo::Divisao,
+                }
             }
             '%' => {
                 chars.next();
-                simbolos.push(Simbolo::Modulo);
+                Simbolo::Modulo
             }
             '(' => {
                 chars.next();
-                simbolos.push(Simbolo::AbreParenteses);
+                Simbolo::AbreParenteses
             }
             ')' => {
                 chars.next();
-                simbolos.push(Simbolo::FechaParenteses);
+                Simbolo::FechaParenteses
             }
             '{' => {
                 chars.next();
-                simbolos.push(Simbolo::AbreChaves);
+                Simbolo::AbreChaves
             }
             '}' => {
                 chars.next();
-                simbolos.push(Simbolo::FechaChaves);
+                Simbolo::FechaChaves
             }
             ',' => {
                 chars.next();
-                simbolos.push(Simbolo::Virgula);
+                Simbolo::Virgula
             }
             ';' => {
                 chars.next();
-                simbolos.push(Simbolo::PontoVirgula);
+                Simbolo::PontoVirgula
             }
             '=' => {
                 chars.next();
-                // Verifica se é ==
-                if let Some(&'=') = chars.peek() {
-                    chars.next();
-                    simbolos.push(Simbolo::IgualIgual);
-                } else {
-                    simbolos.push(Simbolo::Igual);
+                // Verifica se é == ou =>
+                match chars.peek() {
+                    Some(&'=') => {
+                        chars.next();
+                        Simbolo::IgualIgual
+                    }
+                    Some(&'>') => {
+                        chars.next();
+                        Simbolo::Seta
+                    }
+                    _ => Simbolo::Igual,
                 }
             }
             '>' => {
@@ -180,9 +416,9 @@ pub fn analisar(texto: &str) -> Vec<Simbolo> {
                 // Verifica se é >=
                 if let Some(&'=') = chars.peek() {
                     chars.next();
-                    simbolos.push(Simbolo::MaiorIgual);
+                    Simbolo::MaiorIgual
                 } else {
-                    simbolos.push(Simbolo::Maior);
+                    Simbolo::Maior
                 }
             }
             '<' => {
@@ -190,9 +426,9 @@ pub fn analisar(texto: &str) -> Vec<Simbolo> {
                 // Verifica se é <=
                 if let Some(&'=') = chars.peek() {
                     chars.next();
-                    simbolos.push(Simbolo::MenorIgual);
+                    Simbolo::MenorIgual
                 } else {
-                    simbolos.push(Simbolo::Menor);
+                    Simbolo::Menor
                 }
             }
             '!' => {
@@ -200,20 +436,395 @@ pub fn analisar(texto: &str) -> Vec<Simbolo> {
                 // Verifica se é !=
                 if let Some(&'=') = chars.peek() {
                     chars.next();
-                    simbolos.push(Simbolo::Diferente);
+                    Simbolo::Diferente
                 } else {
-                    simbolos.push(Simbolo::Negacao);
+                    Simbolo::Negacao
                 }
             }
-            ' ' | '\n' | '\t' => {
+            ' ' | '\n' | '\t' | '\r' => {
                 chars.next(); // ignora espaços
+                continue;
             }
             outro => {
-                panic!("Caractere inválido: {}", outro);
+                chars.next();
+                let span = chars.span_desde(inicio);
+                erros.push(LexError { tipo: TipoLexError::CaractereInvalido(outro), span });
+                continue;
             }
+        };
+
+        if let Simbolo::Comentario(_) = simbolo
+            && !manter_comentarios
+        {
+            continue;
         }
+        let span = chars.span_desde(inicio);
+        simbolos.push(Token { simbolo, span });
     }
 
-    simbolos.push(Simbolo::Fim);
-    simbolos
-}
\ No newline at end of file
+    simbolos.push(Token { simbolo: Simbolo::Fim, span: chars.marca() });
+    (simbolos, erros)
+}
+
+// Lê um número a partir do primeiro dígito: inteiros decimais, hexadecimais
+// (0xFF), binários (0b1010) e octais (0o17), floats com parte fracionária e/ou
+// expoente (1.5e-3) e '_' entre dígitos como separador (1_000_000). Devolve
+// None quando o número é inválido; o erro já fica registrado em `erros`.
+fn ler_numero(texto: &str, chars: &mut Leitor, inicio: Span, erros: &mut Vec<LexError>) -> Option<Simbolo> {
+    let mut adiante = chars.clone();
+    adiante.next();
+    let base = match (chars.peek(), adiante.peek()) {
+        (Some('0'), Some('x' | 'X')) => 16,
+        (Some('0'), Some('b' | 'B')) => 2,
+        (Some('0'), Some('o' | 'O')) => 8,
+        _ => 10,
+    };
+
+    let resultado = if base == 10 {
+        ler_decimal(chars)
+    } else {
+        chars.next(); // consome o 0
+        chars.next(); // e a letra da base
+        // Lê também letras e dígitos fora da base, para o erro mostrar o número todo
+        let digitos = ler_enquanto(chars, |c| c.is_ascii_alphanumeric() || c == '_');
+        inteiro_na_base(&digitos, base)
+    };
+
+    if let Err(Some(_)) = resultado {
+        // Consome o resto do número mal formado para não gerar outros erros
+        ler_enquanto(chars, |c| c.is_alphanumeric() || c == '_' || c == '.');
+    }
+    let numero = texto[inicio.inicio..chars.posicao].to_string();
+    let span = chars.span_desde(inicio);
+    match resultado {
+        Ok(simbolo) => Some(simbolo),
+        Err(Some(motivo)) => {
+            erros.push(LexError { tipo: TipoLexError::NumeroInvalido { numero, motivo }, span });
+            None
+        }
+        // 2^63 não cabe em i64, mas -2^63 cabe. Como nenhum outro literal é
+        // negativo, ele é guardado como i64::MIN e o analisador sintático só
+        // o aceita logo depois de um menos unário.
+        Err(None) if magnitude(&numero, base) == Some(1 << 63) => Some(Simbolo::NumeroInteiro(i64::MIN)),
+        // Estouro: mantém o número como float para não gerar erros de sintaxe
+        // em cascata
+        Err(None) => {
+            let aproximado = aproximar(&numero, base);
+            erros.push(LexError { tipo: TipoLexError::InteiroMuitoGrande(numero), span });
+            Some(Simbolo::NumeroFloat(aproximado))
+        }
+    }
+}
+
+// Err(Some(motivo)) para números mal formados e Err(None) para inteiros que
+// não cabem em 64 bits
+type LeituraNumero = Result<Simbolo, Option<&'static str>>;
+
+fn ler_decimal(chars: &mut Leitor) -> LeituraNumero {
+    let mut numero = sem_separadores(&ler_enquanto(chars, |c| c.is_ascii_digit() || c == '_'))?;
+    let mut float = false;
+
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        if !chars.peek().is_some_and(char::is_ascii_digit) {
+            return Err(Some("faltam dígitos depois do '.'"));
+        }
+        numero.push('.');
+        numero += &sem_separadores(&ler_enquanto(chars, |c| c.is_ascii_digit() || c == '_'))?;
+        float = true;
+    }
+
+    if let Some(&e @ ('e' | 'E')) = chars.peek() {
+        chars.next();
+        numero.push(e);
+        if let Some(&sinal @ ('+' | '-')) = chars.peek() {
+            chars.next();
+            numero.push(sinal);
+        }
+        let expoente = ler_enquanto(chars, |c| c.is_ascii_digit() || c == '_');
+        if expoente.is_empty() {
+            return Err(Some("faltam dígitos no expoente"));
+        }
+        numero += &sem_separadores(&expoente)?;
+        float = true;
+    }
+
+    // Letras coladas no número, como em 12abc
+    if chars.peek().is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+        ler_enquanto(chars, |c| c.is_alphanumeric() || c == '_');
+        return Err(Some("caractere inválido no número"));
+    }
+
+    if float {
+        numero.parse().map(Simbolo::NumeroFloat).map_err(|_| Some("float mal formado"))
+    } else {
+        numero.parse().map(Simbolo::NumeroInteiro).map_err(|_| None)
+    }
+}
+
+fn inteiro_na_base(digitos: &str, base: u32) -> LeituraNumero {
+    if digitos.is_empty() {
+        return Err(Some("faltam dígitos depois do prefixo"));
+    }
+    let digitos = sem_separadores(digitos)?;
+    if !digitos.chars().all(|c| c.is_digit(base)) {
+        return Err(Some(match base {
+            16 => "dígito hexadecimal inválido",
+            8 => "dígito octal inválido",
+            _ => "dígito binário inválido",
+        }));
+    }
+    i64::from_str_radix(&digitos, base).map(Simbolo::NumeroInteiro).map_err(|_| None)
+}
+
+// Remove os separadores, que só podem aparecer entre dois dígitos
+fn sem_separadores(digitos: &str) -> Result<String, Option<&'static str>> {
+    if digitos.starts_with('_') || digitos.ends_with('_') || digitos.contains("__") {
+        return Err(Some("'_' só pode aparecer entre dígitos"));
+    }
+    Ok(digitos.replace('_', ""))
+}
+
+// Valor de um inteiro que não cabe em i64, se couber em u64
+fn magnitude(numero: &str, base: u32) -> Option<u64> {
+    let digitos = if base == 10 { numero } else { &numero[2..] };
+    u64::from_str_radix(&digitos.replace('_', ""), base).ok()
+}
+
+// Valor aproximado de um inteiro que não cabe em 64 bits
+fn aproximar(numero: &str, base: u32) -> f64 {
+    if base == 10 {
+        return numero.replace('_', "").parse().unwrap_or(f64::INFINITY);
+    }
+    numero[2..]
+        .chars()
+        .filter_map(|c| c.to_digit(base))
+        .fold(0.0, |total, digito| total * base as f64 + digito as f64)
+}
+
+fn ler_enquanto(chars: &mut Leitor, condicao: impl Fn(char) -> bool) -> String {
+    let mut lido = String::new();
+    while let Some(&c) = chars.peek() {
+        if !condicao(c) {
+            break;
+        }
+        lido.push(c);
+        chars.next();
+    }
+    lido
+}
+
+// Lê uma string a partir da aspa de abertura. Strings brutas (r"...") não
+// processam escapes nem interpolações. Strings entre aspas triplas podem
+// ocupar várias linhas e têm a indentação comum às linhas removida.
+fn ler_string(texto: &str, chars: &mut Leitor, inicio: Span, bruta: bool, erros: &mut Vec<LexError>) -> Simbolo {
+    chars.next(); // consome a aspa inicial
+    let mut tripla = false;
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        if chars.peek() != Some(&'"') {
+            return Simbolo::String(String::new()); // ""
+        }
+        chars.next();
+        tripla = true;
+    }
+
+    // Quanto da indentação ainda falta pular na linha atual
+    let mut pular = 0;
+    let mut bloco = Bloco::default();
+    if tripla && let Some(fim) = fim_do_bloco(chars.clone(), bruta) {
+        bloco = Bloco::medir(&texto[chars.posicao..fim]);
+        if bloco.primeira_em_branco {
+            while chars.next().is_some_and(|c| c != '\n') {}
+            pular = bloco.indentacao;
+        }
+    }
+
+    let mut string = String::new();
+    let mut partes = Vec::new();
+    let mut terminada = false;
+    while let Some(&d) = chars.peek() {
+        if pular > 0 && (d == ' ' || d == '\t') {
+            pular -= 1;
+            chars.next();
+            continue;
+        }
+        pular = 0;
+        match d {
+            '"' if !tripla || fecha_tripla(chars) => {
+                // consome a aspa (ou as aspas) final
+                for _ in 0..if tripla { 3 } else { 1 } {
+                    chars.next();
+                }
+                terminada = true;
+                break;
+            }
+            '\n' if tripla => {
+                string.push('\n');
+                chars.next();
+                pular = bloco.indentacao;
+            }
+            '\\' if !bruta => {
+                chars.next(); // consome barra
+                if let Some(&esc) = chars.peek() {
+                    let escaped = match esc {
+                        '"' => '"',
+                        '\\' => '\\',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        _ => esc, // inclui \$, para um '$' literal
+                    };
+                    string.push(escaped);
+                    chars.next();
+                }
+            }
+            '$' if !bruta => {
+                let cifrao = chars.marca();
+                chars.next();
+                if chars.peek() != Some(&'{') {
+                    string.push('$');
+                    continue;
+                }
+                chars.next(); // consome a chave
+                let codigo = chars.marca();
+                if !pular_interpolacao(chars) {
+                    let span = chars.span_desde(cifrao);
+                    erros.push(LexError { tipo: TipoLexError::InterpolacaoNaoTerminada, span });
+                    terminada = true; // o erro acima já explica o problema
+                    break;
+                }
+                // A chave final fica de fora da expressão
+                let trecho = &texto[codigo.inicio..chars.posicao - 1];
+                let (simbolos, erros_codigo) = analisar_trecho(texto, Leitor::em(trecho, codigo), false);
+                erros.extend(erros_codigo);
+                if !string.is_empty() {
+                    partes.push(ParteString::Texto(std::mem::take(&mut string)));
+                }
+                partes.push(ParteString::Codigo(simbolos));
+            }
+            _ => {
+                string.push(d);
+                chars.next();
+            }
+        }
+    }
+    if !terminada {
+        let span = chars.span_desde(inicio);
+        erros.push(LexError { tipo: TipoLexError::StringNaoTerminada, span });
+    }
+    // A linha das aspas de fechamento, quando só tem espaços, não faz parte do texto
+    if bloco.ultima_em_branco
+        && let Some(i) = string.rfind('\n')
+    {
+        string.truncate(i);
+    }
+    if partes.is_empty() {
+        Simbolo::String(string)
+    } else {
+        if !string.is_empty() {
+            partes.push(ParteString::Texto(string));
+        }
+        Simbolo::StringInterpolada(partes)
+    }
+}
+
+// Formato de uma string entre aspas triplas
+#[derive(Default)]
+struct Bloco {
+    // Menor indentação entre as linhas depois da primeira, ignorando linhas
+    // em branco (exceto a das aspas de fechamento)
+    indentacao: usize,
+    // As aspas de abertura são seguidas apenas de espaços e quebra de linha
+    primeira_em_branco: bool,
+    // As aspas de fechamento estão sozinhas na última linha
+    ultima_em_branco: bool,
+}
+
+impl Bloco {
+    // `corpo` é o código entre as aspas triplas
+    fn medir(corpo: &str) -> Bloco {
+        let linhas: Vec<&str> = corpo.split('\n').collect();
+        if linhas.len() < 2 {
+            return Bloco::default();
+        }
+        let ultima = linhas.len() - 1;
+        let mut indentacao = usize::MAX;
+        for (i, linha) in linhas.iter().enumerate().skip(1) {
+            if linha.trim().is_empty() && i != ultima {
+                continue;
+            }
+            let espacos = linha.chars().take_while(|c| *c == ' ' || *c == '\t').count();
+            indentacao = indentacao.min(espacos);
+        }
+        Bloco {
+            indentacao,
+            primeira_em_branco: linhas[0].trim().is_empty(),
+            ultima_em_branco: linhas[ultima].trim().is_empty(),
+        }
+    }
+}
+
+fn fecha_tripla(chars: &Leitor) -> bool {
+    let mut adiante = chars.clone();
+    (0..3).all(|_| adiante.next() == Some('"'))
+}
+
+// Posição das aspas que fecham uma string entre aspas triplas, ou None se ela
+// não for terminada
+fn fim_do_bloco(mut chars: Leitor, bruta: bool) -> Option<usize> {
+    loop {
+        if fecha_tripla(&chars) {
+            return Some(chars.posicao);
+        }
+        match chars.next()? {
+            '\\' if !bruta => {
+                chars.next();
+            }
+            '$' if !bruta && chars.peek() == Some(&'{') => {
+                chars.next();
+                if !pular_interpolacao(&mut chars) {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Avança até a '}' que fecha uma interpolação, consumindo-a. Chaves e strings
+// dentro da expressão (que podem ter suas próprias interpolações) são
+// puladas por inteiro. Devolve false se o texto acabar antes.
+fn pular_interpolacao(chars: &mut Leitor) -> bool {
+    let mut profundidade = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => profundidade += 1,
+            '}' => {
+                profundidade -= 1;
+                if profundidade == 0 {
+                    return true;
+                }
+            }
+            '"' => {
+                while let Some(d) = chars.next() {
+                    match d {
+                        '"' => break,
+                        '\\' => {
+                            chars.next();
+                        }
+                        '$' if chars.peek() == Some(&'{') => {
+                            chars.next();
+                            if !pular_interpolacao(chars) {
+                                return false;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
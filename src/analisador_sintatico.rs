@@ -1,14 +1,62 @@
-use crate::analisador_lexico::Simbolo;
-use crate::arvore_sintatica_abstrata::Expr;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::analisador_lexico::{ParteString, Simbolo, Span, Token};
+use crate::arvore_sintatica_abstrata::{
+    DeclaracaoClasse, DeclaracaoFuncao, Expr, ParteInterpolacao, Stmt, TipoExpr, TipoStmt,
+};
+use crate::diagnostico;
+
+// Erro de sintaxe: o que o analisador esperava, o que encontrou e onde
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub esperado: Vec<String>,
+    pub encontrado: Simbolo,
+    pub span: Span,
+}
+
+impl ParseError {
+    // Mostra o erro no estilo do rustc, com o trecho do código e um marcador
+    // embaixo do símbolo problemático
+    pub fn renderizar(&self, fonte: &str) -> String {
+        diagnostico::renderizar(fonte, self.span, &self.to_string())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.esperado.as_slice() {
+            [] => write!(f, "{} inesperado", self.encontrado),
+            [unico] => write!(f, "esperado {}, encontrado {}", unico, self.encontrado),
+            varios => write!(f, "esperado um de {}, encontrado {}", varios.join(", "), self.encontrado),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 pub struct Analisador {
-    simbolos: Vec<Simbolo>,
+    simbolos: Vec<Token>,
     atual: usize,
+    // Quantas funções envolvem o ponto atual; `return` só é aceito dentro de uma
+    funcoes_abertas: usize,
+    // Quantas classes envolvem o ponto atual; `this` só é aceito dentro de uma
+    classes_abertas: usize,
+    // Idem para classes com superclasse, onde `super` é aceito
+    subclasses_abertas: usize,
+}
+
+// Monta um nó binário cujo span cobre os dois operandos
+fn binaria(tipo: fn(Box<Expr>, Box<Expr>) -> TipoExpr, esq: Expr, dir: Expr) -> Expr {
+    let span = esq.span.unir(&dir.span);
+    Expr::new(tipo(Box::new(esq), Box::new(dir)), span)
 }
 
 impl Analisador {
-    pub fn new(simbolos: Vec<Simbolo>) -> Self {
-        Analisador { simbolos, atual: 0 }
+    pub fn new(mut simbolos: Vec<Token>) -> Self {
+        // Comentários não fazem parte da gramática
+        simbolos.retain(|token| !matches!(token.simbolo, Simbolo::Comentario(_)));
+        Analisador { simbolos, atual: 0, funcoes_abertas: 0, classes_abertas: 0, subclasses_abertas: 0 }
     }
 
     fn avancar(&mut self) {
@@ -18,97 +66,194 @@ impl Analisador {
     }
 
     fn ver(&self) -> Option<&Simbolo> {
-        self.simbolos.get(self.atual)
+        self.simbolos.get(self.atual).map(|token| &token.simbolo)
+    }
+
+    fn span_atual(&self) -> Span {
+        match self.simbolos.get(self.atual).or(self.simbolos.last()) {
+            Some(token) => token.span,
+            None => Span::default(),
+        }
+    }
+
+    fn span_anterior(&self) -> Span {
+        match self.atual.checked_sub(1).and_then(|i| self.simbolos.get(i)) {
+            Some(token) => token.span,
+            None => self.span_atual(),
+        }
+    }
+
+    // Span do símbolo `inicio` até o último símbolo consumido
+    fn span_desde(&self, inicio: Span) -> Span {
+        inicio.unir(&self.span_anterior())
     }
 
     fn consumir(&mut self, esperado: &Simbolo) -> bool {
-        if let Some(atual) = self.ver() {
-            if atual == esperado {
-                self.avancar();
-                return true;
-            }
+        if self.ver() == Some(esperado) {
+            self.avancar();
+            return true;
         }
         false
     }
 
-    pub fn analisar_expressao(&mut self) -> Option<Expr> {
-        // Primeiro verifica se é um bloco
-        if let Some(Simbolo::AbreChaves) = self.ver() {
-            self.analisar_bloco()
-        } else if let Some(Simbolo::If) = self.ver() {
-            self.analisar_if()
-        } else if let Some(Simbolo::While) = self.ver() {
-            self.analisar_while()
-        } else if let Some(Simbolo::Var) = self.ver() {
-            self.analisar_declaracao_variavel()
+    // Como consumir, mas gera um erro quando o símbolo não é o esperado
+    fn esperar(&mut self, esperado: &Simbolo) -> Result<(), ParseError> {
+        if self.consumir(esperado) {
+            Ok(())
         } else {
-            self.analisar_atribuicao()
+            Err(ParseError {
+                esperado: vec![esperado.to_string()],
+                encontrado: self.ver().unwrap_or(&Simbolo::Fim).clone(),
+                span: self.span_atual(),
+            })
         }
     }
 
-    fn analisar_while(&mut self) -> Option<Expr> {
-        // while ( expression ) statement
-        if !self.consumir(&Simbolo::While) {
-            return None;
+    fn erro(&self, esperado: &[&str]) -> ParseError {
+        ParseError {
+            esperado: esperado.iter().map(|e| e.to_string()).collect(),
+            encontrado: self.ver().unwrap_or(&Simbolo::Fim).clone(),
+            span: self.span_atual(),
         }
+    }
 
-        // Consome o parêntese de abertura
-        if !self.consumir(&Simbolo::AbreParenteses) {
-            return None;
+    // Analisa um arquivo inteiro. As declarações são separadas por ';' ou por
+    // quebras de linha. Em caso de erro, o analisador se recupera e continua,
+    // para relatar todos os erros de uma vez.
+    pub fn analisar_programa(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut declaracoes = Vec::new();
+        let mut erros = Vec::new();
+
+        loop {
+            while self.consumir(&Simbolo::PontoVirgula) {}
+            if matches!(self.ver(), Some(Simbolo::Fim) | None) {
+                break;
+            }
+            let resultado = self.analisar_declaracao().and_then(|declaracao| {
+                self.separar_declaracao()?;
+                Ok(declaracao)
+            });
+            match resultado {
+                Ok(declaracao) => declaracoes.push(declaracao),
+                Err(erro) => {
+                    erros.push(erro);
+                    self.sincronizar();
+                }
+            }
         }
 
+        if erros.is_empty() { Ok(declaracoes) } else { Err(erros) }
+    }
+
+    // Depois de uma declaração deve vir ';', uma quebra de linha, o fim do
+    // bloco ou o fim do arquivo. Declarações terminadas em '}' dispensam o
+    // separador, como em `{ var a = 1; } a`.
+    fn separar_declaracao(&mut self) -> Result<(), ParseError> {
+        if self.consumir(&Simbolo::PontoVirgula) {
+            return Ok(());
+        }
+        let terminou_em_chave = self.atual > 0
+            && self.simbolos.get(self.atual - 1).map(|token| &token.simbolo)
+                == Some(&Simbolo::FechaChaves);
+        match self.ver() {
+            Some(Simbolo::FechaChaves) | Some(Simbolo::Fim) | None => Ok(()),
+            _ if terminou_em_chave || self.nova_linha() => Ok(()),
+            _ => Err(self.erro(&["';'", "quebra de linha"])),
+        }
+    }
+
+    // Indica se o símbolo atual está numa linha posterior ao anterior
+    fn nova_linha(&self) -> bool {
+        self.atual > 0 && self.span_atual().linha > self.span_anterior().linha
+    }
+
+    // Descarta símbolos até o provável início da próxima declaração: depois de
+    // um ';', numa nova linha ou numa palavra-chave que inicia declarações
+    fn sincronizar(&mut self) {
+        self.avancar();
+        while let Some(simbolo) = self.ver() {
+            match simbolo {
+                Simbolo::Fim
+                | Simbolo::Var
+                | Simbolo::If
+                | Simbolo::While
+                | Simbolo::Print
+                | Simbolo::Fun
+                | Simbolo::Class
+                | Simbolo::Return => return,
+                _ if self.nova_linha() => return,
+                Simbolo::PontoVirgula => {
+                    self.avancar();
+                    return;
+                }
+                _ => self.avancar(),
+            }
+        }
+    }
+
+    pub fn analisar_expressao(&mut self) -> Result<Expr, ParseError> {
+        self.analisar_atribuicao()
+    }
+
+    fn analisar_while(&mut self) -> Result<Stmt, ParseError> {
+        // while ( expression ) statement
+        let inicio = self.span_atual();
+        self.esperar(&Simbolo::While)?;
+
+        // Consome o parêntese de abertura
+        self.esperar(&Simbolo::AbreParenteses)?;
+
         // Analisa a condição
         let condicao = self.analisar_atribuicao()?;
 
         // Consome o parêntese de fechamento
-        if !self.consumir(&Simbolo::FechaParenteses) {
-            return None;
-        }
+        self.esperar(&Simbolo::FechaParenteses)?;
 
         // Analisa o corpo do while
         let corpo = self.analisar_declaracao()?;
 
-        Some(Expr::While(Box::new(condicao), Box::new(corpo)))
+        let tipo = TipoStmt::While(condicao, Box::new(corpo));
+        Ok(Stmt::new(tipo, self.span_desde(inicio)))
     }
 
-    fn analisar_if(&mut self) -> Option<Expr> {
+    fn analisar_if(&mut self) -> Result<Stmt, ParseError> {
         // if ( expression ) statement else statement
-        if !self.consumir(&Simbolo::If) {
-            return None;
-        }
+        let inicio = self.span_atual();
+        self.esperar(&Simbolo::If)?;
 
         // Consome o parêntese de abertura
-        if !self.consumir(&Simbolo::AbreParenteses) {
-            return None;
-        }
+        self.esperar(&Simbolo::AbreParenteses)?;
 
         // Analisa a condição
         let condicao = self.analisar_atribuicao()?;
 
         // Consome o parêntese de fechamento
-        if !self.consumir(&Simbolo::FechaParenteses) {
-            return None;
-        }
+        self.esperar(&Simbolo::FechaParenteses)?;
 
         // Analisa o ramo then
-        let then_expr = self.analisar_declaracao()?;
+        let then_stmt = self.analisar_declaracao()?;
 
         // Verifica se há um ramo else
-        let else_expr = if let Some(Simbolo::Else) = self.ver() {
+        let else_stmt = if let Some(Simbolo::Else) = self.ver() {
             self.avancar(); // consome o else
             Some(Box::new(self.analisar_declaracao()?))
         } else {
             None
         };
 
-        Some(Expr::If(Box::new(condicao), Box::new(then_expr), else_expr))
+        let tipo = TipoStmt::If(condicao, Box::new(then_stmt), else_stmt);
+        Ok(Stmt::new(tipo, self.span_desde(inicio)))
+    }
+
+    fn analisar_bloco(&mut self) -> Result<Stmt, ParseError> {
+        let inicio = self.span_atual();
+        let declaracoes = self.analisar_corpo_bloco()?;
+        Ok(Stmt::new(TipoStmt::Block(declaracoes), self.span_desde(inicio)))
     }
 
-    fn analisar_bloco(&mut self) -> Option<Expr> {
+    fn analisar_corpo_bloco(&mut self) -> Result<Vec<Stmt>, ParseError> {
         // { declaration* }
-        if !self.consumir(&Simbolo::AbreChaves) {
-            return None;
-        }
+        self.esperar(&Simbolo::AbreChaves)?;
 
         let mut declaracoes = Vec::new();
 
@@ -123,77 +268,250 @@ impl Analisador {
                     self.avancar(); // consome o ;
                     continue; // pula para a próxima declaração
                 }
+                Simbolo::Fim => return Err(self.erro(&["'}'"])),
                 _ => {
-                    // Tenta analisar uma declaração
-                    if let Some(declaracao) = self.analisar_declaracao() {
-                        declaracoes.push(declaracao);
-                    } else {
-                        return None; // erro na declaração
+                    declaracoes.push(self.analisar_declaracao()?);
+                    self.separar_declaracao()?;
+                }
+            }
+        }
+
+        Ok(declaracoes)
+    }
+
+    fn analisar_funcao(&mut self) -> Result<Stmt, ParseError> {
+        // fun IDENTIFIER ( parameters? ) block
+        let inicio = self.span_atual();
+        self.esperar(&Simbolo::Fun)?;
+
+        let nome = self.esperar_identificador("nome da função")?;
+        let parametros = self.analisar_parametros()?;
+        let corpo = self.analisar_corpo_funcao()?;
+
+        let declaracao = DeclaracaoFuncao { nome, parametros, corpo, span: self.span_desde(inicio) };
+        Ok(Stmt::new(TipoStmt::Funcao(Rc::new(declaracao)), self.span_desde(inicio)))
+    }
+
+    fn analisar_classe(&mut self) -> Result<Stmt, ParseError> {
+        // class IDENTIFIER ( < IDENTIFIER )? { ( IDENTIFIER ( parameters? ) block )* }
+        let inicio = self.span_atual();
+        self.esperar(&Simbolo::Class)?;
+        let nome = self.esperar_identificador("nome da classe")?;
+
+        let mut superclasse = None;
+        if self.consumir(&Simbolo::Menor) {
+            let nome_superclasse = self.esperar_identificador("nome da superclasse")?;
+            let tipo = TipoExpr::Identificador(nome_superclasse);
+            superclasse = Some(Expr::new(tipo, self.span_anterior()));
+        }
+        self.esperar(&Simbolo::AbreChaves)?;
+
+        let subclasse = usize::from(superclasse.is_some());
+        self.classes_abertas += 1;
+        self.subclasses_abertas += subclasse;
+        let metodos = self.analisar_metodos();
+        self.classes_abertas -= 1;
+        self.subclasses_abertas -= subclasse;
+
+        let declaracao = DeclaracaoClasse { nome, superclasse, metodos: metodos? };
+        Ok(Stmt::new(TipoStmt::Classe(declaracao), self.span_desde(inicio)))
+    }
+
+    // Métodos são escritos como funções, mas sem o `fun`
+    fn analisar_metodos(&mut self) -> Result<Vec<Rc<DeclaracaoFuncao>>, ParseError> {
+        let mut metodos = Vec::new();
+        while !self.consumir(&Simbolo::FechaChaves) {
+            if let Some(Simbolo::Fim) | None = self.ver() {
+                return Err(self.erro(&["'}'"]));
+            }
+            let inicio = self.span_atual();
+            let nome = self.esperar_identificador("nome do método")?;
+            let parametros = self.analisar_parametros()?;
+            let corpo = self.analisar_corpo_funcao()?;
+            let span = self.span_desde(inicio);
+            metodos.push(Rc::new(DeclaracaoFuncao { nome, parametros, corpo, span }));
+        }
+        Ok(metodos)
+    }
+
+    fn analisar_parametros(&mut self) -> Result<Vec<String>, ParseError> {
+        // ( IDENTIFIER ( , IDENTIFIER )* )
+        self.esperar(&Simbolo::AbreParenteses)?;
+        let mut parametros = Vec::new();
+        if self.ver() != Some(&Simbolo::FechaParenteses) {
+            loop {
+                parametros.push(self.esperar_identificador("nome do parâmetro")?);
+                if !self.consumir(&Simbolo::Virgula) {
+                    break;
+                }
+            }
+        }
+        self.esperar(&Simbolo::FechaParenteses)?;
+        Ok(parametros)
+    }
+
+    fn analisar_corpo_funcao(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        self.funcoes_abertas += 1;
+        let corpo = self.analisar_corpo_bloco();
+        self.funcoes_abertas -= 1;
+        corpo
+    }
+
+    fn analisar_lambda(&mut self) -> Result<Expr, ParseError> {
+        // fun ( parameters? ) block
+        // | ( parameters? ) => ( expression | block )
+        // | IDENTIFIER => ( expression | block )
+        let inicio = self.span_atual();
+        let corpo;
+        let parametros;
+        if self.consumir(&Simbolo::Fun) {
+            parametros = self.analisar_parametros()?;
+            corpo = self.analisar_corpo_funcao()?;
+        } else {
+            parametros = match self.ver() {
+                Some(Simbolo::Identificador(_)) => vec![self.esperar_identificador("parâmetro")?],
+                _ => self.analisar_parametros()?,
+            };
+            self.esperar(&Simbolo::Seta)?;
+            corpo = if self.ver() == Some(&Simbolo::AbreChaves) {
+                self.analisar_corpo_funcao()?
+            } else {
+                // `x => expr` é o mesmo que `fun (x) { return expr; }`
+                let expr = self.analisar_expressao()?;
+                let span = expr.span;
+                vec![Stmt::new(TipoStmt::Return(Some(expr)), span)]
+            };
+        }
+
+        let span = self.span_desde(inicio);
+        let declaracao = DeclaracaoFuncao { nome: "lambda".to_string(), parametros, corpo, span };
+        Ok(Expr::new(TipoExpr::Lambda(Rc::new(declaracao)), span))
+    }
+
+    // Verifica, sem consumir nada, se os próximos símbolos formam o início de
+    // uma lambda com seta: `x =>` ou `(a, b) =>`
+    fn inicio_de_lambda_com_seta(&self) -> bool {
+        let simbolo = |i: usize| self.simbolos.get(self.atual + i).map(|token| &token.simbolo);
+        match simbolo(0) {
+            Some(Simbolo::Identificador(_)) => simbolo(1) == Some(&Simbolo::Seta),
+            Some(Simbolo::AbreParenteses) => {
+                let mut i = 1;
+                loop {
+                    match simbolo(i) {
+                        Some(Simbolo::FechaParenteses) => return simbolo(i + 1) == Some(&Simbolo::Seta),
+                        Some(Simbolo::Identificador(_) | Simbolo::Virgula) => i += 1,
+                        _ => return false,
                     }
                 }
             }
+            _ => false,
         }
+    }
 
-        Some(Expr::Block(declaracoes))
+    fn analisar_return(&mut self) -> Result<Stmt, ParseError> {
+        // return expression?
+        let inicio = self.span_atual();
+        if self.funcoes_abertas == 0 {
+            // `return` fora de uma função
+            return Err(self.erro(&[]));
+        }
+        self.esperar(&Simbolo::Return)?;
+
+        // O valor é opcional: `return` sozinho na linha devolve nil
+        let valor = match self.ver() {
+            Some(Simbolo::PontoVirgula | Simbolo::FechaChaves | Simbolo::Fim) | None => None,
+            _ if self.nova_linha() => None,
+            _ => Some(self.analisar_expressao()?),
+        };
+        Ok(Stmt::new(TipoStmt::Return(valor), self.span_desde(inicio)))
+    }
+
+    fn esperar_identificador(&mut self, descricao: &str) -> Result<String, ParseError> {
+        if let Some(Simbolo::Identificador(nome)) = self.ver() {
+            let nome = nome.clone();
+            self.avancar();
+            Ok(nome)
+        } else {
+            Err(self.erro(&[descricao]))
+        }
     }
 
-    fn analisar_declaracao(&mut self) -> Option<Expr> {
-        // Uma declaração pode ser uma declaração de variável, um bloco ou uma expressão
+    fn analisar_declaracao(&mut self) -> Result<Stmt, ParseError> {
+        // Uma declaração pode ser uma declaração de variável, um bloco, um
+        // controle de fluxo, um print ou uma expressão
         match self.ver() {
             Some(Simbolo::Var) => self.analisar_declaracao_variavel(),
             Some(Simbolo::AbreChaves) => self.analisar_bloco(),
+            Some(Simbolo::If) => self.analisar_if(),
+            // `fun (` começa uma lambda, que é uma expressão
+            Some(Simbolo::Fun) if self.simbolos.get(self.atual + 1).map(|token| &token.simbolo)
+                != Some(&Simbolo::AbreParenteses) =>
+            {
+                self.analisar_funcao()
+            }
+            Some(Simbolo::Class) => self.analisar_classe(),
+            Some(Simbolo::Return) => self.analisar_return(),
+            Some(Simbolo::While) => self.analisar_while(),
             Some(Simbolo::Print) => {
+                let inicio = self.span_atual();
                 self.avancar(); // consome o print
                 let expr = self.analisar_expressao()?;
-                Some(Expr::Print(Box::new(expr)))
+                Ok(Stmt::new(TipoStmt::Print(expr), self.span_desde(inicio)))
+            }
+            _ => {
+                let expr = self.analisar_expressao()?;
+                let span = expr.span;
+                Ok(Stmt::new(TipoStmt::Expressao(expr), span))
             }
-            _ => self.analisar_atribuicao(),
         }
     }
 
-    fn analisar_declaracao_variavel(&mut self) -> Option<Expr> {
+    fn analisar_declaracao_variavel(&mut self) -> Result<Stmt, ParseError> {
         // var IDENTIFIER "=" assignment | var IDENTIFIER
-        if !self.consumir(&Simbolo::Var) {
-            return None;
-        }
+        let inicio = self.span_atual();
+        self.esperar(&Simbolo::Var)?;
 
         // Consome o identificador
-        let nome = if let Some(Simbolo::Identificador(nome)) = self.ver() {
-            let nome = nome.clone();
-            self.avancar();
-            nome
-        } else {
-            return None;
-        };
+        let nome = self.esperar_identificador("identificador")?;
 
         // Verifica se há inicialização
         if let Some(Simbolo::Igual) = self.ver() {
             self.avancar(); // consome o =
             let valor = self.analisar_atribuicao()?;
-            Some(Expr::VarDef(nome, Box::new(valor)))
+            let tipo = TipoStmt::VarDef(nome, Some(valor));
+            Ok(Stmt::new(tipo, self.span_desde(inicio)))
         } else {
-            // Se não há inicialização, trata como var variavel = nil
-            Some(Expr::VarDef(nome, Box::new(Expr::NumeroFloat(0.0)))) // nil = 0.0 para simplificar
+            // Se não há inicialização, a variável começa como nil
+            Ok(Stmt::new(TipoStmt::VarDef(nome, None), self.span_desde(inicio)))
         }
     }
 
-    fn analisar_atribuicao(&mut self) -> Option<Expr> {
-        // assignment  → IDENTIFIER '=' assignment | logic_or ;
+    fn analisar_atribuicao(&mut self) -> Result<Expr, ParseError> {
+        // assignment  → ( call '.' )? IDENTIFIER '=' assignment | logic_or ;
         let expr = self.analisar_or()?;
         if let Some(Simbolo::Igual) = self.ver() {
             self.avancar();
-            if let Expr::Identificador(nome) = expr {
+            if let TipoExpr::Identificador(nome) = expr.tipo {
+                let valor = self.analisar_atribuicao()?;
+                let span = expr.span.unir(&valor.span);
+                return Ok(Expr::new(TipoExpr::Assign(nome, Box::new(valor)), span));
+            } else if let TipoExpr::GetAttr(objeto, nome) = expr.tipo {
                 let valor = self.analisar_atribuicao()?;
-                return Some(Expr::Assign(nome, Box::new(valor)));
+                let span = expr.span.unir(&valor.span);
+                return Ok(Expr::new(TipoExpr::SetAttr(objeto, nome, Box::new(valor)), span));
             } else {
-                // Erro: lado esquerdo não é identificador
-                return None;
+                // Erro: lado esquerdo não é identificador, aponta para o '='
+                return Err(ParseError {
+                    esperado: vec!["fim da expressão".to_string()],
+                    encontrado: Simbolo::Igual,
+                    span: self.span_anterior(),
+                });
             }
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn analisar_or(&mut self) -> Option<Expr> {
+    fn analisar_or(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.analisar_and()?;
 
         while let Some(simbolo) = self.ver() {
@@ -201,16 +519,16 @@ impl Analisador {
                 Simbolo::Or => {
                     self.avancar();
                     let direito = self.analisar_and()?;
-                    expr = Expr::Or(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::Or, expr, direito);
                 }
                 _ => break,
             }
         }
 
-        Some(expr)
+        Ok(expr)
     }
 
-    fn analisar_and(&mut self) -> Option<Expr> {
+    fn analisar_and(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.analisar_comparacao()?;
 
         while let Some(simbolo) = self.ver() {
@@ -218,16 +536,16 @@ impl Analisador {
                 Simbolo::And => {
                     self.avancar();
                     let direito = self.analisar_comparacao()?;
-                    expr = Expr::And(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::And, expr, direito);
                 }
                 _ => break,
             }
         }
 
-        Some(expr)
+        Ok(expr)
     }
 
-    fn analisar_soma(&mut self) -> Option<Expr> {
+    fn analisar_soma(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.analisar_produto()?;
 
         while let Some(simbolo) = self.ver() {
@@ -235,21 +553,23 @@ impl Analisador {
                 Simbolo::Soma => {
                     self.avancar();
                     let direito = self.analisar_produto()?;
-                    expr = Expr::Soma(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::Soma, expr, direito);
                 }
-                Simbolo::Subtracao => {
+                // Um '-' no início de uma linha começa uma nova expressão
+                // (como em `-5`), em vez de continuar a subtração
+                Simbolo::Subtracao if !self.nova_linha() => {
                     self.avancar();
                     let direito = self.analisar_produto()?;
-                    expr = Expr::Subtracao(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::Subtracao, expr, direito);
                 }
                 _ => break,
             }
         }
 
-        Some(expr)
+        Ok(expr)
     }
 
-    fn analisar_comparacao(&mut self) -> Option<Expr> {
+    fn analisar_comparacao(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.analisar_soma()?;
 
         while let Some(simbolo) = self.ver() {
@@ -257,180 +577,221 @@ impl Analisador {
                 Simbolo::Maior => {
                     self.avancar();
                     let direito = self.analisar_soma()?;
-                    expr = Expr::Maior(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::Maior, expr, direito);
                 }
                 Simbolo::MaiorIgual => {
                     self.avancar();
                     let direito = self.analisar_soma()?;
-                    expr = Expr::MaiorIgual(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::MaiorIgual, expr, direito);
                 }
                 Simbolo::Menor => {
                     self.avancar();
                     let direito = self.analisar_soma()?;
-                    expr = Expr::Menor(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::Menor, expr, direito);
                 }
                 Simbolo::MenorIgual => {
                     self.avancar();
                     let direito = self.analisar_soma()?;
-                    expr = Expr::MenorIgual(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::MenorIgual, expr, direito);
                 }
                 Simbolo::IgualIgual => {
                     self.avancar();
                     let direito = self.analisar_soma()?;
-                    expr = Expr::IgualIgual(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::IgualIgual, expr, direito);
                 }
                 Simbolo::Diferente => {
                     self.avancar();
                     let direito = self.analisar_soma()?;
-                    expr = Expr::Diferente(Box::new(expr), Box::new(direito));
+                    expr = binaria(TipoExpr::Diferente, expr, direito);
                 }
                 _ => break,
             }
         }
 
-        Some(expr)
+        Ok(expr)
     }
 
-    fn analisar_produto(&mut self) -> Option<Expr> {
-        let mut expr = self.analisar_atributo()?;
+    fn analisar_produto(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.analisar_chamada()?;
 
         while let Some(simbolo) = self.ver() {
             match simbolo {
                 Simbolo::Multiplicacao => {
                     self.avancar();
-                    let direito = self.analisar_atributo()?;
-                    expr = Expr::Multiplicacao(Box::new(expr), Box::new(direito));
+                    let direito = self.analisar_chamada()?;
+                    expr = binaria(TipoExpr::Multiplicacao, expr, direito);
                 }
                 Simbolo::Divisao => {
                     self.avancar();
-                    let direito = self.analisar_atributo()?;
-                    expr = Expr::Divisao(Box::new(expr), Box::new(direito));
+                    let direito = self.analisar_chamada()?;
+                    expr = binaria(TipoExpr::Divisao, expr, direito);
                 }
                 Simbolo::Modulo => {
                     self.avancar();
-                    let direito = self.analisar_atributo()?;
-                    expr = Expr::Modulo(Box::new(expr), Box::new(direito));
+                    let direito = self.analisar_chamada()?;
+                    expr = binaria(TipoExpr::Modulo, expr, direito);
                 }
                 _ => break,
             }
         }
 
-        Some(expr)
+        Ok(expr)
     }
 
-    fn analisar_unario(&mut self) -> Option<Expr> {
-        match self.ver()? {
+    fn analisar_unario(&mut self) -> Result<Expr, ParseError> {
+        if self.ver() == Some(&Simbolo::Fun) || self.inicio_de_lambda_com_seta() {
+            return self.analisar_lambda();
+        }
+
+        let inicio = self.span_atual();
+        let tipo = match self.ver().unwrap_or(&Simbolo::Fim) {
             Simbolo::Negacao => {
                 self.avancar(); // consome o !
-                let expr = self.analisar_unario()?;
-                Some(Expr::Negacao(Box::new(expr)))
+                let expr = self.analisar_chamada()?;
+                TipoExpr::Negacao(Box::new(expr))
             }
             Simbolo::Subtracao => {
                 self.avancar(); // consome o -
-                let expr = self.analisar_unario()?;
-                Some(Expr::NegacaoAritmetica(Box::new(expr)))
+                // -9223372036854775808 vira o literal i64::MIN, já que o
+                // número sem o sinal não cabe em 64 bits
+                if self.ver() == Some(&Simbolo::NumeroInteiro(i64::MIN)) {
+                    self.avancar();
+                    TipoExpr::NumeroInteiro(i64::MIN)
+                } else {
+                    let expr = self.analisar_chamada()?;
+                    TipoExpr::NegacaoAritmetica(Box::new(expr))
+                }
             }
+            Simbolo::NumeroInteiro(i64::MIN) => return Err(self.erro(&["inteiro de até 64 bits"])),
             Simbolo::NumeroInteiro(n) => {
                 let valor = *n;
                 self.avancar();
-                Some(Expr::NumeroInteiro(valor))
+                TipoExpr::NumeroInteiro(valor)
             }
             Simbolo::NumeroFloat(n) => {
                 let valor = *n;
                 self.avancar();
-                Some(Expr::NumeroFloat(valor))
+                TipoExpr::NumeroFloat(valor)
             }
             Simbolo::String(s) => {
                 let valor = s.clone();
                 self.avancar();
-                Some(Expr::String(valor))
+                TipoExpr::String(valor)
+            }
+            Simbolo::StringInterpolada(partes) => {
+                let partes = partes.clone();
+                self.avancar();
+                TipoExpr::Interpolacao(self.analisar_interpolacao(partes)?)
+            }
+            Simbolo::True | Simbolo::False => {
+                let valor = self.ver() == Some(&Simbolo::True);
+                self.avancar();
+                TipoExpr::Booleano(valor)
+            }
+            Simbolo::Nil => {
+                self.avancar();
+                TipoExpr::Nil
             }
             Simbolo::Identificador(id) => {
                 let nome = id.clone();
                 self.avancar();
-                Some(Expr::Identificador(nome))
+                TipoExpr::Identificador(nome)
+            }
+            // `this` fora de um método
+            Simbolo::This if self.classes_abertas == 0 => return Err(self.erro(&[])),
+            Simbolo::This => {
+                self.avancar();
+                TipoExpr::This
+            }
+            // `super` fora de uma classe com superclasse
+            Simbolo::Super if self.subclasses_abertas == 0 => return Err(self.erro(&[])),
+            Simbolo::Super => {
+                // super . IDENTIFIER
+                self.avancar();
+                self.esperar(&Simbolo::Ponto)?;
+                TipoExpr::Super(self.esperar_identificador("nome do método")?)
             }
             Simbolo::AbreParenteses => {
                 self.avancar();
                 let expr = self.analisar_expressao()?;
-                if self.consumir(&Simbolo::FechaParenteses) {
-                    Some(expr)
-                } else {
-                    None
-                }
+                self.esperar(&Simbolo::FechaParenteses)?;
+                // O span do agrupamento inclui os parênteses
+                expr.tipo
             }
-            _ => None,
-        }
+            _ => return Err(self.erro(&["expressão"])),
+        };
+        Ok(Expr::new(tipo, self.span_desde(inicio)))
     }
 
-    fn analisar_atributo(&mut self) -> Option<Expr> {
-        let mut expr = self.analisar_chamada()?;
-        
-        while let Some(simbolo) = self.ver() {
-            match simbolo {
-                Simbolo::Ponto => {
-                    self.avancar(); // consome o ponto
-                    if let Some(Simbolo::Identificador(attr)) = self.ver() {
-                        let nome_attr = attr.clone();
-                        self.avancar(); // consome o identificador
-                        expr = Expr::GetAttr(Box::new(expr), nome_attr);
-                    } else {
-                        return None; // esperava um identificador após o ponto
+    // Cada expressão de uma string interpolada já vem separada pelo analisador
+    // léxico e é analisada à parte, mas dentro do mesmo contexto (`this` e
+    // `super` continuam valendo dentro de métodos)
+    fn analisar_interpolacao(&self, partes: Vec<ParteString>) -> Result<Vec<ParteInterpolacao>, ParseError> {
+        let mut resultado = Vec::with_capacity(partes.len());
+        for parte in partes {
+            match parte {
+                ParteString::Texto(texto) => resultado.push(ParteInterpolacao::Texto(texto)),
+                ParteString::Codigo(simbolos) => {
+                    let mut analisador = Analisador::new(simbolos);
+                    analisador.funcoes_abertas = self.funcoes_abertas;
+                    analisador.classes_abertas = self.classes_abertas;
+                    analisador.subclasses_abertas = self.subclasses_abertas;
+                    let expr = analisador.analisar_expressao()?;
+                    if analisador.ver() != Some(&Simbolo::Fim) {
+                        return Err(analisador.erro(&["'}'"]));
                     }
+                    resultado.push(ParteInterpolacao::Expressao(expr));
                 }
-                _ => break,
             }
         }
-        
-        Some(expr)
+        Ok(resultado)
     }
 
-    fn analisar_chamada(&mut self) -> Option<Expr> {
+    fn analisar_chamada(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.analisar_unario()?;
         
         while let Some(simbolo) = self.ver() {
             match simbolo {
-                Simbolo::AbreParenteses => {
+                // Assim como no '-', um '(' numa nova linha não é uma chamada
+                Simbolo::AbreParenteses if !self.nova_linha() => {
                     self.avancar(); // consome o parêntese de abertura
                     let mut argumentos = Vec::new();
                     
                     // Verifica se há argumentos
-                    if let Some(simbolo) = self.ver() {
-                        if *simbolo != Simbolo::FechaParenteses {
-                            // Primeiro argumento
-                            if let Some(arg) = self.analisar_expressao() {
-                                argumentos.push(arg);
-                            }
-                            
-                            // Argumentos adicionais separados por vírgula
-                            while let Some(simbolo) = self.ver() {
-                                match simbolo {
-                                    Simbolo::Virgula => {
-                                        self.avancar(); // consome a vírgula
-                                        if let Some(arg) = self.analisar_expressao() {
-                                            argumentos.push(arg);
-                                        } else {
-                                            return None; // esperava um argumento após a vírgula
-                                        }
-                                    }
-                                    Simbolo::FechaParenteses => break,
-                                    _ => return None, // caractere inesperado
+                    if let Some(simbolo) = self.ver()
+                        && *simbolo != Simbolo::FechaParenteses
+                    {
+                        // Primeiro argumento
+                        argumentos.push(self.analisar_expressao()?);
+                        
+                        // Argumentos adicionais separados por vírgula
+                        while let Some(simbolo) = self.ver() {
+                            match simbolo {
+                                Simbolo::Virgula => {
+                                    self.avancar(); // consome a vírgula
+                                    argumentos.push(self.analisar_expressao()?);
                                 }
+                                Simbolo::FechaParenteses => break,
+                                _ => return Err(self.erro(&["','", "')'"])), // símbolo inesperado
                             }
                         }
                     }
-                    
+
                     // Consome o parêntese de fechamento
-                    if self.consumir(&Simbolo::FechaParenteses) {
-                        expr = Expr::Call(Box::new(expr), argumentos);
-                    } else {
-                        return None; // esperava parêntese de fechamento
-                    }
+                    self.esperar(&Simbolo::FechaParenteses)?;
+                    let span = expr.span.unir(&self.span_anterior());
+                    expr = Expr::new(TipoExpr::Call(Box::new(expr), argumentos), span);
+                }
+                Simbolo::Ponto => {
+                    self.avancar(); // consome o ponto
+                    let nome_attr = self.esperar_identificador("nome do atributo")?;
+                    let span = expr.span.unir(&self.span_anterior());
+                    expr = Expr::new(TipoExpr::GetAttr(Box::new(expr), nome_attr), span);
                 }
                 _ => break,
             }
         }
         
-        Some(expr)
+        Ok(expr)
     }
 }
\ No newline at end of file
@@ -1,18 +1,38 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crate::ambiente::{Ambiente, Contexto};
+use crate::analisador_lexico::Span;
+use crate::diagnostico;
+use crate::valor::{Classe, Funcao, Value};
+
+// Nó da árvore sintática junto com o trecho do código de onde ele veio
+#[derive(Debug)]
+pub struct Expr {
+    pub tipo: TipoExpr,
+    pub span: Span,
+}
 
 #[derive(Debug)]
-pub enum Expr {
+pub enum TipoExpr {
     NumeroInteiro(i64),
     NumeroFloat(f64),
     String(String),
+    Interpolacao(Vec<ParteInterpolacao>), // "texto ${expressão}"
+    Booleano(bool),
+    Nil,
     Identificador(String),
     Assign(String, Box<Expr>),
-    VarDef(String, Box<Expr>),   // var variavel = valor
-    Block(Vec<Expr>),            // { declarações }
-    If(Box<Expr>, Box<Expr>, Option<Box<Expr>>), // if (condição) then else
-    While(Box<Expr>, Box<Expr>), // while (condição) corpo
     GetAttr(Box<Expr>, String), // obj.atributo
+    SetAttr(Box<Expr>, String, Box<Expr>), // obj.atributo = valor
+    This,                       // this (só dentro de métodos)
+    Super(String),              // super.metodo (só dentro de subclasses)
     Call(Box<Expr>, Vec<Expr>), // funcao(argumentos)
+    Lambda(Rc<DeclaracaoFuncao>), // fun (x) { ... } ou (x) => expressão
     Negacao(Box<Expr>),          // !value
     NegacaoAritmetica(Box<Expr>), // -value (criado pelo parser quando - está em contexto unário)
     And(Box<Expr>, Box<Expr>),
@@ -28,256 +48,851 @@ pub enum Expr {
     MenorIgual(Box<Expr>, Box<Expr>),
     IgualIgual(Box<Expr>, Box<Expr>),
     Diferente(Box<Expr>, Box<Expr>),
-    Print(Box<Expr>), // Para a instrução print
     // Para contexto/escopo HashTable RC(Reference Count) - Celula
 }
 
-impl Expr {
-    pub fn avaliar_com_contexto(&self, ctx: &mut HashMap<String, f64>) -> f64 {
+#[derive(Debug)]
+pub enum ParteInterpolacao {
+    Texto(String),
+    Expressao(Expr),
+}
+
+// Declarações não produzem valores; apenas expressões produzem
+#[derive(Debug)]
+pub struct Stmt {
+    pub tipo: TipoStmt,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum TipoStmt {
+    Expressao(Expr),                          // expressão usada como declaração
+    VarDef(String, Option<Expr>),             // var variavel = valor (sem valor = nil)
+    Print(Expr),                              // print valor
+    Block(Vec<Stmt>),                         // { declarações }
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),   // if (condição) then else
+    While(Expr, Box<Stmt>),                   // while (condição) corpo
+    Funcao(Rc<DeclaracaoFuncao>),             // fun nome(parametros) { corpo }
+    Return(Option<Expr>),                     // return valor
+    Classe(DeclaracaoClasse),                 // class Nome < Superclasse { metodos }
+}
+
+// Compartilhada (Rc) entre a árvore e os valores de função criados a partir dela
+#[derive(Debug)]
+pub struct DeclaracaoFuncao {
+    pub nome: String,
+    pub parametros: Vec<String>,
+    pub corpo: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct DeclaracaoClasse {
+    pub nome: String,
+    pub superclasse: Option<Expr>,
+    pub metodos: Vec<Rc<DeclaracaoFuncao>>,
+}
+
+// Motivo pelo qual a execução de uma declaração foi interrompida: um erro ou
+// um `return`, que sobe até a chamada de função mais próxima
+#[derive(Debug, Clone, PartialEq)]
+pub enum Interrupcao {
+    Erro(RuntimeError),
+    Retorno(Value),
+}
+
+impl From<RuntimeError> for Interrupcao {
+    fn from(erro: RuntimeError) -> Self {
+        Interrupcao::Erro(erro)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub tipo: TipoErroExecucao,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoErroExecucao {
+    VariavelIndefinida(String),
+    OperandosInvalidos {
+        operador: &'static str,
+        esquerda: &'static str,
+        direita: &'static str,
+    },
+    OperandoInvalido {
+        operador: &'static str,
+        tipo: &'static str,
+    },
+    DivisaoPorZero,
+    NaoChamavel(&'static str),
+    NumeroDeArgumentos {
+        esperado: usize,
+        recebido: usize,
+    },
+    EstouroDePilha(usize), // limite de níveis de aninhamento
+    AtributoDesconhecido {
+        tipo: &'static str,
+        nome: String,
+    },
+    AtributoIndefinido {
+        classe: String,
+        nome: String,
+    },
+    SemCampos(&'static str),
+    SuperclasseInvalida(&'static str),
+    ArgumentoInvalido {
+        funcao: String,
+        esperado: &'static str,
+        recebido: &'static str,
+    },
+    ErroNativo(String),
+    ErroDeSaida(String),
+}
+
+impl fmt::Display for TipoErroExecucao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Expr::NumeroInteiro(n) => *n as f64,
-            Expr::NumeroFloat(n) => *n,
-            Expr::String(_) => 0.0,
-            Expr::Identificador(nome) => {
-                match nome.as_str() {
-                    "true" => 1.0,
-                    "false" => 0.0,
-                    _ => *ctx.get(nome).unwrap_or(&0.0),
-                }
-            }
-            Expr::Assign(nome, expr) => {
-                let valor = expr.avaliar_com_contexto(ctx);
-                ctx.insert(nome.clone(), valor);
-                valor
+            TipoErroExecucao::VariavelIndefinida(nome) => {
+                write!(f, "variável '{}' não foi definida", nome)
+            }
+            TipoErroExecucao::OperandosInvalidos { operador, esquerda, direita } => write!(
+                f,
+                "operador '{}' não suporta operandos do tipo {} e {}",
+                operador, esquerda, direita
+            ),
+            TipoErroExecucao::OperandoInvalido { operador, tipo } => {
+                write!(f, "operador '{}' não suporta operando do tipo {}", operador, tipo)
+            }
+            TipoErroExecucao::DivisaoPorZero => write!(f, "divisão por zero"),
+            TipoErroExecucao::NaoChamavel(tipo) => {
+                write!(f, "valor do tipo {} não pode ser chamado", tipo)
+            }
+            TipoErroExecucao::NumeroDeArgumentos { esperado, recebido } => {
+                write!(f, "esperado {} argumento(s), recebido {}", esperado, recebido)
+            }
+            TipoErroExecucao::EstouroDePilha(limite) => {
+                write!(f, "limite de {} níveis de aninhamento excedido", limite)
+            }
+            TipoErroExecucao::AtributoDesconhecido { tipo, nome } => {
+                write!(f, "valor do tipo {} não possui o atributo '{}'", tipo, nome)
+            }
+            TipoErroExecucao::AtributoIndefinido { classe, nome } => {
+                write!(f, "instância de {} não possui o atributo '{}'", classe, nome)
+            }
+            TipoErroExecucao::SemCampos(tipo) => {
+                write!(f, "valor do tipo {} não possui campos", tipo)
+            }
+            TipoErroExecucao::ArgumentoInvalido { funcao, esperado, recebido } => write!(
+                f,
+                "função {} esperava argumento do tipo {}, recebido {}",
+                funcao, esperado, recebido
+            ),
+            TipoErroExecucao::ErroNativo(mensagem) => write!(f, "{}", mensagem),
+            TipoErroExecucao::ErroDeSaida(mensagem) => write!(f, "erro ao escrever a saída: {}", mensagem),
+            TipoErroExecucao::SuperclasseInvalida(tipo) => {
+                write!(f, "superclasse deve ser uma classe, encontrado valor do tipo {}", tipo)
             }
-            Expr::VarDef(nome, expr) => {
-                let valor = expr.avaliar_com_contexto(ctx);
-                ctx.insert(nome.clone(), valor);
-                valor
-            }
-            Expr::Block(declaracoes) => {
-                let mut resultado = 0.0;
-                for declaracao in declaracoes {
-                    resultado = declaracao.avaliar_com_contexto(ctx);
+        }
+    }
+}
+
+impl RuntimeError {
+    pub fn renderizar(&self, fonte: &str) -> String {
+        diagnostico::renderizar(fonte, self.span, &self.to_string())
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tipo)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+// Aplica uma operação aritmética: inteiro com inteiro continua inteiro (se não
+// estourar), qualquer combinação com float vira float
+fn aritmetica(
+    operador: &'static str,
+    esq: Value,
+    dir: Value,
+    op_inteiro: fn(i64, i64) -> Option<i64>,
+    op_float: fn(f64, f64) -> f64,
+) -> Result<Value, TipoErroExecucao> {
+    match (&esq, &dir) {
+        (Value::Inteiro(a), Value::Inteiro(b)) => Ok(match op_inteiro(*a, *b) {
+            Some(n) => Value::Inteiro(n),
+            None => Value::Float(op_float(*a as f64, *b as f64)),
+        }),
+        _ => match (esq.como_float(), dir.como_float()) {
+            (Some(a), Some(b)) => Ok(Value::Float(op_float(a, b))),
+            _ => Err(operandos_invalidos(operador, &esq, &dir)),
+        },
+    }
+}
+
+// Divisão e módulo falham quando o divisor é zero, em vez de gerar inf ou NaN
+fn divisao(
+    operador: &'static str,
+    esq: Value,
+    dir: Value,
+    op_inteiro: fn(i64, i64) -> Option<i64>,
+    op_float: fn(f64, f64) -> f64,
+) -> Result<Value, TipoErroExecucao> {
+    if dir.como_float() == Some(0.0) && esq.como_float().is_some() {
+        return Err(TipoErroExecucao::DivisaoPorZero);
+    }
+    aritmetica(operador, esq, dir, op_inteiro, op_float)
+}
+
+// Números são comparados numericamente e strings em ordem lexicográfica.
+// Comparações com NaN são sempre falsas.
+fn comparacao(
+    operador: &'static str,
+    esq: Value,
+    dir: Value,
+    op: fn(Ordering) -> bool,
+) -> Result<Value, TipoErroExecucao> {
+    let ordem = match (&esq, &dir) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => match (esq.como_float(), dir.como_float()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => return Err(operandos_invalidos(operador, &esq, &dir)),
+        },
+    };
+    Ok(Value::Booleano(ordem.is_some_and(op)))
+}
+
+// Junta o texto com os valores das expressões, mostrados como no `print`.
+// Fica fora de `avaliar_com_contexto` pelo mesmo motivo de `avaliar_binaria`.
+fn avaliar_interpolacao(partes: &[ParteInterpolacao], ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+    let mut resultado = String::new();
+    for parte in partes {
+        match parte {
+            ParteInterpolacao::Texto(texto) => resultado.push_str(texto),
+            ParteInterpolacao::Expressao(expr) => resultado.push_str(&expr.avaliar_com_contexto(ctx)?.to_string()),
+        }
+    }
+    Ok(Value::String(resultado))
+}
+
+fn operandos_invalidos(operador: &'static str, esq: &Value, dir: &Value) -> TipoErroExecucao {
+    TipoErroExecucao::OperandosInvalidos {
+        operador,
+        esquerda: esq.nome_tipo(),
+        direita: dir.nome_tipo(),
+    }
+}
+
+impl Expr {
+    pub fn new(tipo: TipoExpr, span: Span) -> Self {
+        Expr { tipo, span }
+    }
+
+    fn erro(&self, tipo: TipoErroExecucao) -> RuntimeError {
+        RuntimeError { tipo, span: self.span }
+    }
+
+    // Toda avaliação passa por aqui. Cada nível de aninhamento (de expressões,
+    // declarações ou chamadas de função) ocupa pilha do Rust, então o nível é
+    // contado para que aninhamentos profundos demais virem um erro em vez de
+    // estourar a pilha do próprio interpretador.
+    pub fn avaliar_com_contexto(&self, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        if ctx.profundidade >= ctx.profundidade_maxima {
+            return Err(self.erro(TipoErroExecucao::EstouroDePilha(ctx.profundidade_maxima)));
+        }
+        ctx.profundidade += 1;
+        let resultado = self.avaliar_no(ctx);
+        ctx.profundidade -= 1;
+        resultado
+    }
+
+    // Os casos maiores ficam em funções separadas para manter pequeno o quadro
+    // de pilha desta função, que é reentrada a cada nível de aninhamento
+    fn avaliar_no(&self, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        match &self.tipo {
+            TipoExpr::NumeroInteiro(n) => Ok(Value::Inteiro(*n)),
+            TipoExpr::NumeroFloat(n) => Ok(Value::Float(*n)),
+            TipoExpr::String(s) => Ok(Value::String(s.clone())),
+            TipoExpr::Interpolacao(partes) => avaliar_interpolacao(partes, ctx),
+            TipoExpr::Booleano(b) => Ok(Value::Booleano(*b)),
+            TipoExpr::Nil => Ok(Value::Nil),
+            TipoExpr::Identificador(nome) => self.obter_variavel(nome, ctx),
+            TipoExpr::Assign(nome, expr) => self.atribuir(nome, expr, ctx),
+            TipoExpr::GetAttr(obj, attr) => self.obter_atributo(obj, attr, ctx),
+            TipoExpr::SetAttr(obj, attr, expr) => self.definir_atributo(obj, attr, expr, ctx),
+            TipoExpr::This => self.obter_variavel("this", ctx),
+            TipoExpr::Super(metodo) => self.obter_metodo_super(metodo, ctx),
+            TipoExpr::Call(callee, args) => self.avaliar_chamada(callee, args, ctx),
+            TipoExpr::Lambda(declaracao) => Ok(criar_funcao(declaracao, ctx)),
+            TipoExpr::Negacao(expr) | TipoExpr::NegacaoAritmetica(expr) => self.avaliar_unaria(expr, ctx),
+            TipoExpr::And(esq, dir) | TipoExpr::Or(esq, dir) => self.avaliar_logica(esq, dir, ctx),
+            TipoExpr::Soma(esq, dir)
+            | TipoExpr::Subtracao(esq, dir)
+            | TipoExpr::Multiplicacao(esq, dir)
+            | TipoExpr::Divisao(esq, dir)
+            | TipoExpr::Modulo(esq, dir)
+            | TipoExpr::Maior(esq, dir)
+            | TipoExpr::Menor(esq, dir)
+            | TipoExpr::MaiorIgual(esq, dir)
+            | TipoExpr::MenorIgual(esq, dir)
+            | TipoExpr::IgualIgual(esq, dir)
+            | TipoExpr::Diferente(esq, dir) => self.avaliar_binaria(esq, dir, ctx),
+        }
+    }
+
+    fn avaliar_binaria(&self, esq: &Expr, dir: &Expr, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let a = esq.avaliar_com_contexto(ctx)?;
+        let b = dir.avaliar_com_contexto(ctx)?;
+        match &self.tipo {
+            TipoExpr::Soma(..) => match (a, b) {
+                (Value::String(a), Value::String(b)) => Ok(Value::String(a + &b)),
+                (a, b) => aritmetica("+", a, b, i64::checked_add, |a, b| a + b),
+            },
+            TipoExpr::Subtracao(..) => aritmetica("-", a, b, i64::checked_sub, |a, b| a - b),
+            TipoExpr::Multiplicacao(..) => aritmetica("*", a, b, i64::checked_mul, |a, b| a * b),
+            // A divisão sempre produz float, como em 7 / 2 = 3.5
+            TipoExpr::Divisao(..) => divisao("/", a, b, |_, _| None, |a, b| a / b),
+            TipoExpr::Modulo(..) => divisao("%", a, b, i64::checked_rem, |a, b| a % b),
+            TipoExpr::Maior(..) => comparacao(">", a, b, Ordering::is_gt),
+            TipoExpr::Menor(..) => comparacao("<", a, b, Ordering::is_lt),
+            TipoExpr::MaiorIgual(..) => comparacao(">=", a, b, Ordering::is_ge),
+            TipoExpr::MenorIgual(..) => comparacao("<=", a, b, Ordering::is_le),
+            TipoExpr::IgualIgual(..) => Ok(Value::Booleano(a.igual(&b))),
+            TipoExpr::Diferente(..) => Ok(Value::Booleano(!a.igual(&b))),
+            _ => unreachable!("avaliar_binaria chamada com {:?}", self.tipo),
+        }
+        .map_err(|tipo| self.erro(tipo))
+    }
+
+    fn obter_variavel(&self, nome: &str, ctx: &Contexto) -> Result<Value, RuntimeError> {
+        ctx.obter(nome)
+            .ok_or_else(|| self.erro(TipoErroExecucao::VariavelIndefinida(nome.to_string())))
+    }
+
+    fn atribuir(&self, nome: &str, expr: &Expr, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let valor = expr.avaliar_com_contexto(ctx)?;
+        if ctx.atribuir(nome, valor.clone()) {
+            Ok(valor)
+        } else {
+            Err(self.erro(TipoErroExecucao::VariavelIndefinida(nome.to_string())))
+        }
+    }
+
+    fn definir_atributo(&self, obj: &Expr, nome: &str, expr: &Expr, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let valor = obj.avaliar_com_contexto(ctx)?;
+        let Value::Instancia(instancia) = valor else {
+            return Err(self.erro(TipoErroExecucao::SemCampos(valor.nome_tipo())));
+        };
+        let novo = expr.avaliar_com_contexto(ctx)?;
+        instancia.borrow_mut().campos.insert(nome.to_string(), novo.clone());
+        Ok(novo)
+    }
+
+    fn avaliar_chamada(&self, callee: &Expr, args: &[Expr], ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let valor = callee.avaliar_com_contexto(ctx)?;
+        let mut argumentos = Vec::with_capacity(args.len());
+        for arg in args {
+            argumentos.push(arg.avaliar_com_contexto(ctx)?);
+        }
+        self.chamar(valor, argumentos, ctx)
+    }
+
+    fn avaliar_unaria(&self, expr: &Expr, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let valor = expr.avaliar_com_contexto(ctx)?;
+        match (&self.tipo, valor) {
+            (TipoExpr::Negacao(_), valor) => Ok(Value::Booleano(!valor.eh_verdadeiro())),
+            (_, Value::Inteiro(n)) => Ok(match n.checked_neg() {
+                Some(n) => Value::Inteiro(n),
+                None => Value::Float(-(n as f64)),
+            }),
+            (_, Value::Float(n)) => Ok(Value::Float(-n)),
+            (_, outro) => Err(self.erro(TipoErroExecucao::OperandoInvalido {
+                operador: "-",
+                tipo: outro.nome_tipo(),
+            })),
+        }
+    }
+
+    // `and` e `or` só avaliam o lado direito quando o esquerdo não decide o
+    // resultado
+    fn avaliar_logica(&self, esq: &Expr, dir: &Expr, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let valor = esq.avaliar_com_contexto(ctx)?;
+        let decidido = match self.tipo {
+            TipoExpr::And(..) => !valor.eh_verdadeiro(),
+            _ => valor.eh_verdadeiro(),
+        };
+        if decidido {
+            Ok(valor)
+        } else {
+            dir.avaliar_com_contexto(ctx)
+        }
+    }
+
+    // Campos da instância têm prioridade sobre os métodos da classe
+    fn obter_atributo(&self, obj: &Expr, nome: &str, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let valor = obj.avaliar_com_contexto(ctx)?;
+        let Value::Instancia(instancia) = &valor else {
+            return Err(self.erro(TipoErroExecucao::AtributoDesconhecido {
+                tipo: valor.nome_tipo(),
+                nome: nome.to_string(),
+            }));
+        };
+        let instancia_ref = instancia.borrow();
+        if let Some(campo) = instancia_ref.campos.get(nome) {
+            return Ok(campo.clone());
+        }
+        match instancia_ref.classe.metodo(nome) {
+            Some(metodo) => Ok(Value::Funcao(Rc::new(metodo.vincular(valor.clone(), ctx)))),
+            None => Err(self.erro(TipoErroExecucao::AtributoIndefinido {
+                classe: instancia_ref.classe.nome.clone(),
+                nome: nome.to_string(),
+            })),
+        }
+    }
+
+    // `super.metodo` começa a busca na superclasse da classe em que o método
+    // atual foi declarado, mas vincula o método à instância atual
+    fn obter_metodo_super(&self, nome: &str, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+        let (Some(Value::Classe(superclasse)), Some(instancia)) = (ctx.obter("super"), ctx.obter("this"))
+        else {
+            return Err(self.erro(TipoErroExecucao::VariavelIndefinida("super".to_string())));
+        };
+        match superclasse.metodo(nome) {
+            Some(metodo) => Ok(Value::Funcao(Rc::new(metodo.vincular(instancia, ctx)))),
+            None => Err(self.erro(TipoErroExecucao::AtributoIndefinido {
+                classe: superclasse.nome.clone(),
+                nome: nome.to_string(),
+            })),
+        }
+    }
+
+    // Chama `valor` com os argumentos já avaliados; `self` é o nó da chamada,
+    // usado para apontar a posição dos erros
+    fn chamar(
+        &self,
+        valor: Value,
+        argumentos: Vec<Value>,
+        ctx: &mut Contexto,
+    ) -> Result<Value, RuntimeError> {
+        match valor {
+            Value::Funcao(funcao) => self.chamar_funcao(&funcao, argumentos, ctx),
+            Value::Nativa(nativa) => {
+                if argumentos.len() != nativa.aridade {
+                    return Err(self.erro(TipoErroExecucao::NumeroDeArgumentos {
+                        esperado: nativa.aridade,
+                        recebido: argumentos.len(),
+                    }));
                 }
-                resultado
-            }
-            Expr::If(condicao, then_expr, else_expr) => {
-                let valor_condicao = condicao.avaliar_com_contexto(ctx);
-                if valor_condicao != 0.0 {
-                    // condição é verdadeira
-                    then_expr.avaliar_com_contexto(ctx)
-                } else {
-                    // condição é falsa
-                    match else_expr {
-                        Some(expr) => expr.avaliar_com_contexto(ctx),
-                        None => 0.0, // nil
-                    }
+                (nativa.funcao)(argumentos).map_err(|tipo| self.erro(tipo))
+            }
+            Value::Classe(classe) => {
+                if argumentos.len() != classe.aridade() {
+                    return Err(self.erro(TipoErroExecucao::NumeroDeArgumentos {
+                        esperado: classe.aridade(),
+                        recebido: argumentos.len(),
+                    }));
                 }
-            }
-            Expr::While(condicao, corpo) => {
-                let mut resultado = 0.0;
-                while condicao.avaliar_com_contexto(ctx) != 0.0 {
-                    resultado = corpo.avaliar_com_contexto(ctx);
+                let instancia = Value::Instancia(ctx.nova_instancia(Rc::clone(&classe)));
+                // O valor devolvido pelo init é descartado: chamar a classe
+                // sempre produz a instância
+                if let Some(init) = classe.metodo("init") {
+                    self.chamar_funcao(&init.vincular(instancia.clone(), ctx), argumentos, ctx)?;
                 }
-                resultado
+                Ok(instancia)
             }
-            Expr::GetAttr(obj, attr) => {
-                println!("Acessando atributo '{}' do objeto", attr);
-                0.0
+            outro => Err(self.erro(TipoErroExecucao::NaoChamavel(outro.nome_tipo()))),
+        }
+    }
+
+    fn chamar_funcao(
+        &self,
+        funcao: &Funcao,
+        argumentos: Vec<Value>,
+        ctx: &mut Contexto,
+    ) -> Result<Value, RuntimeError> {
+        if argumentos.len() != funcao.aridade() {
+            return Err(self.erro(TipoErroExecucao::NumeroDeArgumentos {
+                esperado: funcao.aridade(),
+                recebido: argumentos.len(),
+            }));
+        }
+        // Cada chamada ganha um ambiente novo, filho do ambiente em que
+        // a função foi declarada, com os parâmetros ligados aos argumentos
+        let mut ambiente = Ambiente::com_pai(Rc::clone(&funcao.closure));
+        for (parametro, argumento) in funcao.declaracao.parametros.iter().zip(argumentos) {
+            ambiente.definir(parametro, argumento);
+        }
+
+        let resultado = ctx.no_ambiente(ambiente, |ctx| {
+            executar_bloco(&funcao.declaracao.corpo, ctx)
+        });
+
+        match resultado {
+            Ok(_) => Ok(Value::Nil),
+            Err(Interrupcao::Retorno(valor)) => Ok(valor),
+            Err(Interrupcao::Erro(erro)) => Err(erro),
+        }
+    }
+
+    // Mantém o método antigo para compatibilidade
+    pub fn avaliar(&self) -> Result<Value, RuntimeError> {
+        let mut ctx = Contexto::new();
+        self.avaliar_com_contexto(&mut ctx)
+    }
+}
+
+impl Expr {
+    pub fn imprimir(&self, saida: &mut dyn Write, nivel: usize) -> io::Result<()> {
+        let indent = "   ".repeat(nivel);
+        match &self.tipo {
+            TipoExpr::NumeroInteiro(n) => writeln!(saida, "{}Número Inteiro: {}", indent, n)?,
+            TipoExpr::NumeroFloat(n) => writeln!(saida, "{}Número Float: {}", indent, n)?,
+            TipoExpr::String(s) => writeln!(saida, "{}String: \"{}\"", indent, s)?,
+            TipoExpr::Interpolacao(partes) => {
+                writeln!(saida, "{}Interpolação:", indent)?;
+                for parte in partes {
+                    match parte {
+                        ParteInterpolacao::Texto(texto) => writeln!(saida, "{}   Texto: \"{}\"", indent, texto)?,
+                        ParteInterpolacao::Expressao(expr) => expr.imprimir(saida, nivel + 1)?,
+                    }
+                }
             }
-            Expr::Call(callee, args) => {
-                println!("Chamando função com {} argumentos", args.len());
+            TipoExpr::Booleano(b) => writeln!(saida, "{}Booleano: {}", indent, b)?,
+            TipoExpr::Nil => writeln!(saida, "{}Nil", indent)?,
+            TipoExpr::Identificador(id) => writeln!(saida, "{}Identificador: {}", indent, id)?,
+            TipoExpr::Assign(nome, expr) => {
+                writeln!(saida, "{}Assign: {} =", indent, nome)?;
+                expr.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::GetAttr(obj, attr) => {
+                writeln!(saida, "{}GetAttr: {}", indent, attr)?;
+                obj.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::SetAttr(obj, attr, valor) => {
+                writeln!(saida, "{}SetAttr: {} =", indent, attr)?;
+                obj.imprimir(saida, nivel + 1)?;
+                valor.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::This => writeln!(saida, "{}This", indent)?,
+            TipoExpr::Super(metodo) => writeln!(saida, "{}Super: {}", indent, metodo)?,
+            TipoExpr::Call(callee, args) => {
+                writeln!(saida, "{}Call:", indent)?;
+                callee.imprimir(saida, nivel + 1)?;
                 for (i, arg) in args.iter().enumerate() {
-                    println!("  Argumento {}: {}", i, arg.avaliar_com_contexto(ctx));
+                    writeln!(saida, "{}Argumento {}:", indent, i)?;
+                    arg.imprimir(saida, nivel + 1)?;
                 }
-                0.0
             }
-            Expr::Negacao(expr) => {
-                let valor = expr.avaliar_com_contexto(ctx);
-                if valor == 0.0 { 1.0 } else { 0.0 }
-            }
-            Expr::NegacaoAritmetica(expr) => -expr.avaliar_com_contexto(ctx),
-            Expr::And(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) == 0.0 {
-                    0.0
-                } else {
-                    dir.avaliar_com_contexto(ctx)
+            TipoExpr::Lambda(declaracao) => {
+                writeln!(saida, "{}Lambda({}):", indent, declaracao.parametros.join(", "))?;
+                for declaracao in &declaracao.corpo {
+                    declaracao.imprimir(saida, nivel + 1)?;
                 }
             }
-            Expr::Or(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) != 0.0 {
-                    1.0
+            TipoExpr::Negacao(expr) => {
+                writeln!(saida, "{}Negação (!):", indent)?;
+                expr.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::NegacaoAritmetica(expr) => {
+                writeln!(saida, "{}Negação Aritmética (-):", indent)?;
+                expr.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::And(esq, dir) => {
+                writeln!(saida, "{}And:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Or(esq, dir) => {
+                writeln!(saida, "{}Or:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Soma(esq, dir) => {
+                writeln!(saida, "{}Soma:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Subtracao(esq, dir) => {
+                writeln!(saida, "{}Subtração:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?; // <- estava faltando argumento
+            }
+            TipoExpr::Multiplicacao(esq, dir) => {
+                writeln!(saida, "{}Multiplicação:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Divisao(esq, dir) => {
+                writeln!(saida, "{}Divisão:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Modulo(esq, dir) => {
+                writeln!(saida, "{}Modulo:", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Maior(esq, dir) => {
+                writeln!(saida, "{}Maior (>):", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Menor(esq, dir) => {
+                writeln!(saida, "{}Menor (<):", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::MaiorIgual(esq, dir) => {
+                writeln!(saida, "{}Maior Igual (>=):", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::MenorIgual(esq, dir) => {
+                writeln!(saida, "{}Menor Igual (<=):", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::IgualIgual(esq, dir) => {
+                writeln!(saida, "{}Igual (==):", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+            TipoExpr::Diferente(esq, dir) => {
+                writeln!(saida, "{}Diferente (!=):", indent)?;
+                esq.imprimir(saida, nivel + 1)?;
+                dir.imprimir(saida, nivel + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+fn executar_bloco(declaracoes: &[Stmt], ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+    let mut resultado = None;
+    for declaracao in declaracoes {
+        if let Some(valor) = declaracao.executar(ctx)? {
+            resultado = Some(valor);
+        }
+    }
+    Ok(resultado)
+}
+
+// Valor de `var x = expr` e `return expr`; sem expressão, nil
+fn avaliar_opcional(expr: Option<&Expr>, ctx: &mut Contexto) -> Result<Value, RuntimeError> {
+    match expr {
+        Some(expr) => expr.avaliar_com_contexto(ctx),
+        None => Ok(Value::Nil),
+    }
+}
+
+// Função (declarada ou anônima) que captura o escopo atual
+fn criar_funcao(declaracao: &Rc<DeclaracaoFuncao>, ctx: &mut Contexto) -> Value {
+    let funcao = Funcao {
+        declaracao: Rc::clone(declaracao),
+        closure: ctx.capturar(Rc::clone(&ctx.ambiente)),
+    };
+    Value::Funcao(Rc::new(funcao))
+}
+
+fn executar_if(
+    condicao: &Expr,
+    then_stmt: &Stmt,
+    else_stmt: Option<&Stmt>,
+    ctx: &mut Contexto,
+) -> Result<Option<Value>, Interrupcao> {
+    if condicao.avaliar_com_contexto(ctx)?.eh_verdadeiro() {
+        then_stmt.executar(ctx)
+    } else {
+        match else_stmt {
+            Some(stmt) => stmt.executar(ctx),
+            None => Ok(None),
+        }
+    }
+}
+
+fn executar_while(condicao: &Expr, corpo: &Stmt, ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+    let mut resultado = None;
+    while condicao.avaliar_com_contexto(ctx)?.eh_verdadeiro() {
+        if let Some(valor) = corpo.executar(ctx)? {
+            resultado = Some(valor);
+        }
+    }
+    Ok(resultado)
+}
+
+fn executar_classe(declaracao: &DeclaracaoClasse, ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+    let superclasse = match &declaracao.superclasse {
+        Some(expr) => match expr.avaliar_com_contexto(ctx)? {
+            Value::Classe(classe) => Some(classe),
+            outro => {
+                let tipo = TipoErroExecucao::SuperclasseInvalida(outro.nome_tipo());
+                return Err(RuntimeError { tipo, span: expr.span }.into());
+            }
+        },
+        None => None,
+    };
+
+    // Os métodos de uma subclasse enxergam `super` num ambiente próprio, entre
+    // o ambiente da declaração e o do `this`
+    let closure = match &superclasse {
+        Some(superclasse) => {
+            let mut ambiente = Ambiente::com_pai(Rc::clone(&ctx.ambiente));
+            ambiente.definir("super", Value::Classe(Rc::clone(superclasse)));
+            Rc::new(RefCell::new(ambiente))
+        }
+        None => Rc::clone(&ctx.ambiente),
+    };
+    let closure = ctx.capturar(closure);
+    let metodos: HashMap<String, Rc<Funcao>> = declaracao
+        .metodos
+        .iter()
+        .map(|metodo| {
+            let funcao = Funcao {
+                declaracao: Rc::clone(metodo),
+                closure: Rc::clone(&closure),
+            };
+            (metodo.nome.clone(), Rc::new(funcao))
+        })
+        .collect();
+    let classe = Classe { nome: declaracao.nome.clone(), superclasse, metodos };
+    ctx.definir(&declaracao.nome, Value::Classe(Rc::new(classe)));
+    Ok(None)
+}
+
+impl Stmt {
+    pub fn new(tipo: TipoStmt, span: Span) -> Self {
+        Stmt { tipo, span }
+    }
+
+    // Executa a declaração. O retorno é o valor da última expressão avaliada
+    // como declaração (se houver), usado para mostrar o resultado do programa.
+    // Assim como em `Expr::avaliar_com_contexto`, o nível de aninhamento é
+    // contado.
+    pub fn executar(&self, ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+        if ctx.profundidade >= ctx.profundidade_maxima {
+            let tipo = TipoErroExecucao::EstouroDePilha(ctx.profundidade_maxima);
+            return Err(RuntimeError { tipo, span: self.span }.into());
+        }
+        ctx.profundidade += 1;
+        let resultado = self.executar_no(ctx);
+        ctx.profundidade -= 1;
+        resultado
+    }
+
+    fn executar_no(&self, ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+        match &self.tipo {
+            TipoStmt::Expressao(expr) => Ok(Some(expr.avaliar_com_contexto(ctx)?)),
+            TipoStmt::VarDef(nome, expr) => self.executar_var(nome, expr.as_ref(), ctx),
+            TipoStmt::Print(expr) => self.executar_print(expr, ctx),
+            TipoStmt::Block(declaracoes) => {
+                if ctx.blocos_compartilham_escopo {
+                    executar_bloco(declaracoes, ctx)
                 } else {
-                    if dir.avaliar_com_contexto(ctx) != 0.0 { 1.0 } else { 0.0 }
+                    ctx.em_novo_escopo(|ctx| executar_bloco(declaracoes, ctx))
                 }
             }
-            Expr::Soma(esq, dir) => esq.avaliar_com_contexto(ctx) + dir.avaliar_com_contexto(ctx),
-            Expr::Subtracao(esq, dir) => esq.avaliar_com_contexto(ctx) - dir.avaliar_com_contexto(ctx),
-            Expr::Multiplicacao(esq, dir) => esq.avaliar_com_contexto(ctx) * dir.avaliar_com_contexto(ctx),
-            Expr::Divisao(esq, dir) => esq.avaliar_com_contexto(ctx) / dir.avaliar_com_contexto(ctx),
-            Expr::Modulo(esq, dir) => esq.avaliar_com_contexto(ctx) % dir.avaliar_com_contexto(ctx),
-            Expr::Maior(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) > dir.avaliar_com_contexto(ctx) { 1.0 } else { 0.0 }
-            }
-            Expr::Menor(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) < dir.avaliar_com_contexto(ctx) { 1.0 } else { 0.0 }
-            }
-            Expr::MaiorIgual(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) >= dir.avaliar_com_contexto(ctx) { 1.0 } else { 0.0 }
-            }
-            Expr::MenorIgual(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) <= dir.avaliar_com_contexto(ctx) { 1.0 } else { 0.0 }
-            }
-            Expr::IgualIgual(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) == dir.avaliar_com_contexto(ctx) { 1.0 } else { 0.0 }
-            }
-            Expr::Diferente(esq, dir) => {
-                if esq.avaliar_com_contexto(ctx) != dir.avaliar_com_contexto(ctx) { 1.0 } else { 0.0 }
-            }
-            Expr::Print(expr) => {
-                let valor = expr.avaliar_com_contexto(ctx);
-                println!("{}", valor);
-                valor
+            TipoStmt::If(condicao, then_stmt, else_stmt) => executar_if(condicao, then_stmt, else_stmt.as_deref(), ctx),
+            TipoStmt::While(condicao, corpo) => executar_while(condicao, corpo, ctx),
+            TipoStmt::Funcao(declaracao) => {
+                let funcao = criar_funcao(declaracao, ctx);
+                ctx.definir(&declaracao.nome, funcao);
+                Ok(None)
             }
+            TipoStmt::Classe(declaracao) => executar_classe(declaracao, ctx),
+            TipoStmt::Return(expr) => Err(Interrupcao::Retorno(avaliar_opcional(expr.as_ref(), ctx)?)),
         }
     }
 
-    // Mantém o método antigo para compatibilidade
-    pub fn avaliar(&self) -> f64 {
-        let mut ctx = HashMap::new();
-        self.avaliar_com_contexto(&mut ctx)
+    fn executar_var(&self, nome: &str, expr: Option<&Expr>, ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+        let valor = avaliar_opcional(expr, ctx)?;
+        ctx.definir(nome, valor);
+        Ok(None)
     }
-}
 
-impl Expr {
-    pub fn imprimir(&self, nivel: usize) {
+    fn executar_print(&self, expr: &Expr, ctx: &mut Contexto) -> Result<Option<Value>, Interrupcao> {
+        let valor = expr.avaliar_com_contexto(ctx)?;
+        writeln!(ctx.saida, "{}", valor).map_err(|erro| RuntimeError {
+            tipo: TipoErroExecucao::ErroDeSaida(erro.to_string()),
+            span: self.span,
+        })?;
+        Ok(None)
+    }
+
+    pub fn imprimir(&self, saida: &mut dyn Write, nivel: usize) -> io::Result<()> {
         let indent = "   ".repeat(nivel);
-        match self {
-            Expr::NumeroInteiro(n) => println!("{}Número Inteiro: {}", indent, n),
-            Expr::NumeroFloat(n) => println!("{}Número Float: {}", indent, n),
-            Expr::String(s) => println!("{}String: \"{}\"", indent, s),
-            Expr::Identificador(id) => println!("{}Identificador: {}", indent, id),
-            Expr::Assign(nome, expr) => {
-                println!("{}Assign: {} =", indent, nome);
-                expr.imprimir(nivel + 1);
-            }
-            Expr::VarDef(nome, expr) => {
-                println!("{}VarDef: var {} =", indent, nome);
-                expr.imprimir(nivel + 1);
-            }
-            Expr::Block(declaracoes) => {
-                println!("{}Block:", indent);
+        match &self.tipo {
+            TipoStmt::Expressao(expr) => expr.imprimir(saida, nivel)?,
+            TipoStmt::VarDef(nome, expr) => match expr {
+                Some(expr) => {
+                    writeln!(saida, "{}VarDef: var {} =", indent, nome)?;
+                    expr.imprimir(saida, nivel + 1)?;
+                }
+                None => writeln!(saida, "{}VarDef: var {}", indent, nome)?,
+            },
+            TipoStmt::Print(expr) => {
+                writeln!(saida, "{}Print:", indent)?;
+                expr.imprimir(saida, nivel + 1)?;
+            }
+            TipoStmt::Block(declaracoes) => {
+                writeln!(saida, "{}Block:", indent)?;
                 for declaracao in declaracoes {
-                    declaracao.imprimir(nivel + 1);
+                    declaracao.imprimir(saida, nivel + 1)?;
                 }
             }
-            Expr::If(condicao, then_expr, else_expr) => {
-                println!("{}If:", indent);
-                condicao.imprimir(nivel + 1);
-                println!("{}Then:", indent);
-                then_expr.imprimir(nivel + 1);
-                if let Some(expr) = else_expr {
-                    println!("{}Else:", indent);
-                    expr.imprimir(nivel + 1);
+            TipoStmt::If(condicao, then_stmt, else_stmt) => {
+                writeln!(saida, "{}If:", indent)?;
+                condicao.imprimir(saida, nivel + 1)?;
+                writeln!(saida, "{}Then:", indent)?;
+                then_stmt.imprimir(saida, nivel + 1)?;
+                if let Some(stmt) = else_stmt {
+                    writeln!(saida, "{}Else:", indent)?;
+                    stmt.imprimir(saida, nivel + 1)?;
                 }
             }
-            Expr::While(condicao, corpo) => {
-                println!("{}While:", indent);
-                condicao.imprimir(nivel + 1);
-                println!("{}Corpo:", indent);
-                corpo.imprimir(nivel + 1);
-            }
-            Expr::GetAttr(obj, attr) => {
-                println!("{}GetAttr: {}", indent, attr);
-                obj.imprimir(nivel + 1);
+            TipoStmt::While(condicao, corpo) => {
+                writeln!(saida, "{}While:", indent)?;
+                condicao.imprimir(saida, nivel + 1)?;
+                writeln!(saida, "{}Corpo:", indent)?;
+                corpo.imprimir(saida, nivel + 1)?;
+            }
+            TipoStmt::Funcao(declaracao) => {
+                writeln!(
+                    saida,
+                    "{}Função: {}({})",
+                    indent,
+                    declaracao.nome,
+                    declaracao.parametros.join(", ")
+                )?;
+                for declaracao in &declaracao.corpo {
+                    declaracao.imprimir(saida, nivel + 1)?;
+                }
             }
-            Expr::Call(callee, args) => {
-                println!("{}Call:", indent);
-                callee.imprimir(nivel + 1);
-                for (i, arg) in args.iter().enumerate() {
-                    println!("{}Argumento {}:", indent, i);
-                    arg.imprimir(nivel + 1);
+            TipoStmt::Classe(declaracao) => {
+                match &declaracao.superclasse {
+                    Some(superclasse) => {
+                        writeln!(saida, "{}Classe: {} <", indent, declaracao.nome)?;
+                        superclasse.imprimir(saida, nivel + 1)?;
+                    }
+                    None => writeln!(saida, "{}Classe: {}", indent, declaracao.nome)?,
+                }
+                for metodo in &declaracao.metodos {
+                    writeln!(saida, "{}   Método: {}({})", indent, metodo.nome, metodo.parametros.join(", "))?;
+                    for declaracao in &metodo.corpo {
+                        declaracao.imprimir(saida, nivel + 2)?;
+                    }
                 }
             }
-            Expr::Negacao(expr) => {
-                println!("{}Negação (!):", indent);
-                expr.imprimir(nivel + 1);
-            }
-            Expr::NegacaoAritmetica(expr) => {
-                println!("{}Negação Aritmética (-):", indent);
-                expr.imprimir(nivel + 1);
-            }
-            Expr::And(esq, dir) => {
-                println!("{}And:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Or(esq, dir) => {
-                println!("{}Or:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Soma(esq, dir) => {
-                println!("{}Soma:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Subtracao(esq, dir) => {
-                println!("{}Subtração:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1); // <- estava faltando argumento
-            }
-            Expr::Multiplicacao(esq, dir) => {
-                println!("{}Multiplicação:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Divisao(esq, dir) => {
-                println!("{}Divisão:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Modulo(esq, dir) => {
-                println!("{}Modulo:", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Maior(esq, dir) => {
-                println!("{}Maior (>):", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Menor(esq, dir) => {
-                println!("{}Menor (<):", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::MaiorIgual(esq, dir) => {
-                println!("{}Maior Igual (>=):", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::MenorIgual(esq, dir) => {
-                println!("{}Menor Igual (<=):", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::IgualIgual(esq, dir) => {
-                println!("{}Igual (==):", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Diferente(esq, dir) => {
-                println!("{}Diferente (!=):", indent);
-                esq.imprimir(nivel + 1);
-                dir.imprimir(nivel + 1);
-            }
-            Expr::Print(expr) => {
-                println!("{}Print:", indent);
-                expr.imprimir(nivel + 1);
+            TipoStmt::Return(expr) => {
+                writeln!(saida, "{}Return:", indent)?;
+                if let Some(expr) = expr {
+                    expr.imprimir(saida, nivel + 1)?;
+                }
             }
         }
+        Ok(())
     }
 }
-
@@ -1,12 +1,184 @@
+use std::env;
 use std::fs;
-use mini_lox_rust::executar;
+use std::io::{self, Read};
+use std::process::ExitCode;
 
-fn main() {
-    println!("Coé mundo!");
-    let source = fs::read_to_string("leiaesse.crl")
-        .expect("Erro ao ler esse crl!");
+use mini_lox_rust::analisador_lexico::analisar;
+use mini_lox_rust::analisador_sintatico::Analisador;
+use mini_lox_rust::repl::Repl;
+use mini_lox_rust::{Interpreter, InterpreterError};
 
-    // Mostra o conteúdo na tela
-    println!("Conteúdo do arquivo:\n{}", source);
-    executar(&source);
-} 
\ No newline at end of file
+const USO: &str = "uso: mini_lox [opções] [run] <arquivo | ->
+       mini_lox [opções] -e <código>
+       mini_lox               (sem argumentos, abre o modo interativo)
+
+  -           lê o programa da entrada padrão
+  -e <código> executa o código passado como argumento
+  --tokens    mostra os símbolos do programa em vez de executá-lo
+  --ast       mostra a árvore sintática do programa em vez de executá-lo
+  --trace     mostra símbolos, árvore e resultados durante a execução
+  -h, --help  mostra esta mensagem";
+
+// Códigos de saída no estilo do sysexits.h
+const ERRO_DE_USO: u8 = 64;
+const ERRO_DE_SINTAXE: u8 = 65;
+const ENTRADA_INEXISTENTE: u8 = 66;
+const ERRO_DE_EXECUCAO: u8 = 70;
+
+enum Entrada {
+    Arquivo(String),
+    Codigo(String),
+    Stdin,
+}
+
+enum Acao {
+    Executar,
+    MostrarSimbolos,
+    MostrarArvore,
+}
+
+struct Opcoes {
+    entrada: Entrada,
+    acao: Acao,
+    rastrear: bool,
+}
+
+fn main() -> ExitCode {
+    let argumentos: Vec<String> = env::args().skip(1).collect();
+    if argumentos.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USO);
+        return ExitCode::SUCCESS;
+    }
+    if argumentos.is_empty() {
+        return match Repl::new(io::stdout(), io::stderr()).executar(io::stdin().lock()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(erro) => {
+                eprintln!("erro: {}", erro);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let opcoes = match ler_opcoes(argumentos) {
+        Ok(opcoes) => opcoes,
+        Err(mensagem) => {
+            eprintln!("erro: {}\n\n{}", mensagem, USO);
+            return ExitCode::from(ERRO_DE_USO);
+        }
+    };
+
+    let source = match ler_entrada(&opcoes.entrada) {
+        Ok(source) => source,
+        Err((nome, erro)) => {
+            eprintln!("erro: não foi possível ler {}: {}", nome, erro);
+            return ExitCode::from(ENTRADA_INEXISTENTE);
+        }
+    };
+
+    let resultado = match opcoes.acao {
+        Acao::Executar => {
+            let mut interpretador = Interpreter::new();
+            interpretador.set_trace(opcoes.rastrear);
+            interpretador.run(&source).map(|_| ())
+        }
+        Acao::MostrarSimbolos => mostrar_simbolos(&source),
+        Acao::MostrarArvore => mostrar_arvore(&source),
+    };
+
+    match resultado {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(erro) => {
+            eprint!("{}", erro.renderizar(&source));
+            match erro {
+                InterpreterError::Lexico(_) | InterpreterError::Sintaxe(_) => ExitCode::from(ERRO_DE_SINTAXE),
+                InterpreterError::Execucao(_) => ExitCode::from(ERRO_DE_EXECUCAO),
+            }
+        }
+    }
+}
+
+fn ler_opcoes(argumentos: Vec<String>) -> Result<Opcoes, String> {
+    let mut entrada = None;
+    let mut acao = Acao::Executar;
+    let mut rastrear = false;
+
+    let mut argumentos = argumentos.into_iter().peekable();
+    // `run` é opcional: `mini_lox run x.crl` e `mini_lox x.crl` são equivalentes
+    if argumentos.peek().map(String::as_str) == Some("run") {
+        argumentos.next();
+    }
+    while let Some(argumento) = argumentos.next() {
+        let nova_entrada = match argumento.as_str() {
+            "--tokens" => {
+                acao = Acao::MostrarSimbolos;
+                continue;
+            }
+            "--ast" => {
+                acao = Acao::MostrarArvore;
+                continue;
+            }
+            "--trace" => {
+                rastrear = true;
+                continue;
+            }
+            "-e" => match argumentos.next() {
+                Some(codigo) => Entrada::Codigo(codigo),
+                None => return Err("-e precisa de um código".to_string()),
+            },
+            "-" => Entrada::Stdin,
+            opcao if opcao.starts_with('-') => return Err(format!("opção desconhecida {}", opcao)),
+            arquivo => Entrada::Arquivo(arquivo.to_string()),
+        };
+        if entrada.replace(nova_entrada).is_some() {
+            return Err("apenas um programa pode ser executado por vez".to_string());
+        }
+    }
+
+    match entrada {
+        Some(entrada) => Ok(Opcoes { entrada, acao, rastrear }),
+        None => Err("nenhum programa informado".to_string()),
+    }
+}
+
+// Em caso de erro, devolve junto o nome da entrada para a mensagem
+fn ler_entrada(entrada: &Entrada) -> Result<String, (String, io::Error)> {
+    match entrada {
+        Entrada::Arquivo(caminho) => fs::read_to_string(caminho).map_err(|erro| (caminho.clone(), erro)),
+        Entrada::Codigo(codigo) => Ok(codigo.clone()),
+        Entrada::Stdin => {
+            let mut source = String::new();
+            match io::stdin().read_to_string(&mut source) {
+                Ok(_) => Ok(source),
+                Err(erro) => Err(("<stdin>".to_string(), erro)),
+            }
+        }
+    }
+}
+
+fn mostrar_simbolos(source: &str) -> Result<(), InterpreterError> {
+    let (simbolos, erros) = analisar(source);
+    if !erros.is_empty() {
+        return Err(InterpreterError::Lexico(erros));
+    }
+    for token in &simbolos {
+        println!("{}:{}\t{}", token.span.linha, token.span.coluna, token.simbolo);
+    }
+    Ok(())
+}
+
+fn mostrar_arvore(source: &str) -> Result<(), InterpreterError> {
+    let (simbolos, erros) = analisar(source);
+    if !erros.is_empty() {
+        return Err(InterpreterError::Lexico(erros));
+    }
+    let programa = Analisador::new(simbolos)
+        .analisar_programa()
+        .map_err(InterpreterError::Sintaxe)?;
+    let mut saida = io::stdout();
+    for declaracao in &programa {
+        // Se o stdout foi fechado (por exemplo, num pipe), não há o que mostrar
+        if declaracao.imprimir(&mut saida, 0).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
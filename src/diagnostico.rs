@@ -0,0 +1,46 @@
+use crate::analisador_lexico::Span;
+
+// Monta uma mensagem de erro no estilo do rustc:
+//
+// erro: esperado ')', encontrado número 10
+//  --> linha 1, coluna 7
+//   |
+// 1 | if (x 10
+//   |       ^^
+pub fn renderizar(fonte: &str, span: Span, mensagem: &str) -> String {
+    let linha = fonte.lines().nth(span.linha.saturating_sub(1)).unwrap_or("");
+    let margem = " ".repeat(span.linha.to_string().len());
+
+    // Mantém as tabulações para que o marcador fique alinhado com o texto
+    let recuo: String = linha
+        .chars()
+        .take(span.coluna.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    // O marcador cobre o span, limitado ao fim da linha, com pelo menos um ^
+    let inicio_na_linha = linha
+        .char_indices()
+        .nth(span.coluna.saturating_sub(1))
+        .map_or(linha.len(), |(i, _)| i);
+    let restante = &linha[inicio_na_linha..];
+    let tamanho = restante
+        .char_indices()
+        .take_while(|(i, _)| *i < span.tamanho)
+        .count()
+        .max(1);
+
+    format!(
+        "erro: {}\n{}--> linha {}, coluna {}\n{} |\n{} | {}\n{} | {}{}\n",
+        mensagem,
+        margem,
+        span.linha,
+        span.coluna,
+        margem,
+        span.linha,
+        linha,
+        margem,
+        recuo,
+        "^".repeat(tamanho)
+    )
+}
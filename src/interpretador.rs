@@ -0,0 +1,219 @@
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::ambiente::Contexto;
+use crate::analisador_lexico::LexError;
+use crate::analisador_sintatico::ParseError;
+use crate::arvore_sintatica_abstrata::{RuntimeError, TipoErroExecucao};
+use crate::executar_programa;
+use crate::valor::{FuncaoNativa, Value};
+
+// Interpretador para ser embutido em programas Rust. Mantém as variáveis
+// globais entre uma execução e outra e devolve os erros em vez de imprimi-los.
+//
+//     let mut interpretador = Interpreter::new();
+//     interpretador.register_fn("dobro", |x: i64| x * 2);
+//     interpretador.run("var y = dobro(21)").unwrap();
+//     assert_eq!(interpretador.get_global("y"), Some(Value::Inteiro(42)));
+pub struct Interpreter {
+    contexto: Contexto,
+}
+
+// Falha de uma execução, separada pela etapa em que aconteceu
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpreterError {
+    Lexico(Vec<LexError>),
+    Sintaxe(Vec<ParseError>),
+    Execucao(RuntimeError),
+}
+
+impl InterpreterError {
+    pub fn renderizar(&self, fonte: &str) -> String {
+        match self {
+            InterpreterError::Lexico(erros) => erros.iter().map(|erro| erro.renderizar(fonte)).collect(),
+            InterpreterError::Sintaxe(erros) => erros.iter().map(|erro| erro.renderizar(fonte)).collect(),
+            InterpreterError::Execucao(erro) => erro.renderizar(fonte),
+        }
+    }
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mensagens: Vec<String> = match self {
+            InterpreterError::Lexico(erros) => erros.iter().map(|erro| erro.to_string()).collect(),
+            InterpreterError::Sintaxe(erros) => erros.iter().map(|erro| erro.to_string()).collect(),
+            InterpreterError::Execucao(erro) => vec![erro.to_string()],
+        };
+        write!(f, "{}", mensagens.join("\n"))
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter { contexto: Contexto::new() }
+    }
+
+    // Executa um programa inteiro e devolve o valor da última expressão
+    // (ou nil). Erros de execução não desfazem o que já foi executado.
+    pub fn run(&mut self, source: &str) -> Result<Value, InterpreterError> {
+        executar_programa(source, &mut self.contexto)
+    }
+
+    // Redireciona a saída do `print`, que por padrão vai para o stdout
+    pub fn set_output(&mut self, saida: impl Write + 'static) {
+        self.contexto.saida = Box::new(saida);
+    }
+
+    // Ativa o rastreamento: símbolos, árvore sintática e resultado de cada
+    // declaração. Desativado por padrão.
+    pub fn set_trace(&mut self, ativo: bool) {
+        self.contexto.rastreamento = ativo;
+    }
+
+    // Destino do rastreamento, por padrão o stdout
+    pub fn set_trace_output(&mut self, saida: impl Write + 'static) {
+        self.contexto.depuracao = Box::new(saida);
+    }
+
+    pub fn define_global(&mut self, nome: &str, valor: impl Into<Value>) {
+        self.contexto.globais.borrow_mut().definir(nome, valor.into());
+    }
+
+    pub fn get_global(&self, nome: &str) -> Option<Value> {
+        self.contexto.globais.borrow().obter(nome)
+    }
+
+    // Registra uma closure Rust como função global. Os argumentos são
+    // convertidos para os tipos dos parâmetros da closure, com erro de
+    // execução quando o programa passa um valor de outro tipo.
+    pub fn register_fn<Args, F: HostFunction<Args> + 'static>(&mut self, nome: &str, funcao: F) {
+        let nome_funcao = nome.to_string();
+        let nativa = FuncaoNativa {
+            nome: nome.to_string(),
+            aridade: F::ARIDADE,
+            funcao: Box::new(move |argumentos| funcao.chamar(&nome_funcao, argumentos)),
+        };
+        self.define_global(nome, Value::Nativa(Rc::new(nativa)));
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+// Conversão de um argumento da linguagem para um tipo Rust
+pub trait FromValue: Sized {
+    const NOME_TIPO: &'static str;
+    fn from_value(valor: Value) -> Option<Self>;
+}
+
+impl FromValue for Value {
+    const NOME_TIPO: &'static str = "any";
+    fn from_value(valor: Value) -> Option<Self> {
+        Some(valor)
+    }
+}
+
+impl FromValue for i64 {
+    const NOME_TIPO: &'static str = "int";
+    fn from_value(valor: Value) -> Option<Self> {
+        match valor {
+            Value::Inteiro(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+// Inteiros também são aceitos onde se espera um float
+impl FromValue for f64 {
+    const NOME_TIPO: &'static str = "number";
+    fn from_value(valor: Value) -> Option<Self> {
+        valor.como_float()
+    }
+}
+
+impl FromValue for bool {
+    const NOME_TIPO: &'static str = "bool";
+    fn from_value(valor: Value) -> Option<Self> {
+        match valor {
+            Value::Booleano(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    const NOME_TIPO: &'static str = "string";
+    fn from_value(valor: Value) -> Option<Self> {
+        match valor {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+// Conversão do retorno da closure. `Result<T, String>` permite que a função
+// falhe; a mensagem vira um erro de execução.
+pub trait IntoValue {
+    fn into_value(self) -> Result<Value, TipoErroExecucao>;
+}
+
+impl<T: Into<Value>> IntoValue for T {
+    fn into_value(self) -> Result<Value, TipoErroExecucao> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<Value>> IntoValue for Result<T, String> {
+    fn into_value(self) -> Result<Value, TipoErroExecucao> {
+        self.map(Into::into).map_err(TipoErroExecucao::ErroNativo)
+    }
+}
+
+// Closures que podem ser registradas com `register_fn`; `Args` é a tupla dos
+// tipos dos parâmetros
+pub trait HostFunction<Args> {
+    const ARIDADE: usize;
+    fn chamar(&self, nome: &str, argumentos: Vec<Value>) -> Result<Value, TipoErroExecucao>;
+}
+
+fn converter<T: FromValue>(nome: &str, valor: Value) -> Result<T, TipoErroExecucao> {
+    let recebido = valor.nome_tipo();
+    T::from_value(valor).ok_or_else(|| TipoErroExecucao::ArgumentoInvalido {
+        funcao: nome.to_string(),
+        esperado: T::NOME_TIPO,
+        recebido,
+    })
+}
+
+macro_rules! host_function {
+    ($aridade:expr; $($tipo:ident),*) => {
+        impl<F, R, $($tipo),*> HostFunction<($($tipo,)*)> for F
+        where
+            F: Fn($($tipo),*) -> R,
+            R: IntoValue,
+            $($tipo: FromValue,)*
+        {
+            const ARIDADE: usize = $aridade;
+
+            #[allow(non_snake_case, unused_variables, unused_mut)]
+            fn chamar(&self, nome: &str, argumentos: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+                // A aridade já foi conferida por quem chama
+                let mut argumentos = argumentos.into_iter();
+                $(let $tipo = converter::<$tipo>(nome, argumentos.next().unwrap_or(Value::Nil))?;)*
+                self($($tipo),*).into_value()
+            }
+        }
+    };
+}
+
+host_function!(0;);
+host_function!(1; A);
+host_function!(2; A, B);
+host_function!(3; A, B, C);
+host_function!(4; A, B, C, D);
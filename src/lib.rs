@@ -1,31 +1,95 @@
+pub mod ambiente;
 pub mod analisador_lexico;
 pub mod arvore_sintatica_abstrata;
 pub mod analisador_sintatico;
+pub mod valor;
+pub mod diagnostico;
+pub mod nativas;
+pub mod interpretador;
+pub mod saida;
+pub mod repl;
+use std::io::Write;
+
 use analisador_lexico::analisar;
+use crate::ambiente::Contexto;
 use crate::analisador_sintatico::Analisador;
+use crate::arvore_sintatica_abstrata::Interrupcao;
+use crate::valor::Value;
+
+pub use crate::interpretador::{Interpreter, InterpreterError};
 
 // use lexer::tokenize;
 
-pub fn executar(source: &str) -> f64 {
-    let mut resultado_final = 0.0;
-    let mut contexto = std::collections::HashMap::new();
-
-    for (i, linha) in source.lines().enumerate() {
-        if linha.trim().is_empty() { continue; }
-        println!("\nExpressão linha {}: {}", i + 1, linha);
-        let simbolos = analisar(linha);
-        let mut analisador = Analisador::new(simbolos.clone());
-        match analisador.analisar_expressao() {
-            Some(ast) => {
-                ast.imprimir(0);
-                println!("Símbolos encontrados: {:?}", simbolos);
-                resultado_final = ast.avaliar_com_contexto(&mut contexto);
-                println!("Resultado da expressão: {}", resultado_final);
-            },
-            None => {
-                println!("Erro na análise sintática da linha {}", i + 1);
+pub fn executar(source: &str) -> Value {
+    executar_com_contexto(source, &mut Contexto::new())
+}
+
+// Como `executar`, mas mostrando os símbolos, a árvore sintática de cada
+// declaração e os resultados intermediários
+pub fn executar_com_rastreamento(source: &str) -> Value {
+    let mut contexto = Contexto::new();
+    contexto.rastreamento = true;
+    executar_com_contexto(source, &mut contexto)
+}
+
+// Executa o programa usando um contexto existente, o que permite manter as
+// variáveis entre execuções ou ativar opções como `blocos_compartilham_escopo`.
+// Erros são escritos em `contexto.diagnosticos`, e o resultado é o da última
+// expressão executada antes do erro.
+pub fn executar_com_contexto(source: &str, contexto: &mut Contexto) -> Value {
+    let mut resultado = Value::Nil;
+    if let Err(erro) = executar_registrando_resultado(source, contexto, &mut resultado) {
+        let _ = write!(contexto.diagnosticos, "{}", erro.renderizar(source));
+    }
+    resultado
+}
+
+// Analisa e executa o programa inteiro, devolvendo o valor da última
+// expressão. Um erro léxico ou sintático impede a execução; um erro de
+// execução interrompe o restante do programa.
+pub fn executar_programa(source: &str, contexto: &mut Contexto) -> Result<Value, InterpreterError> {
+    let mut resultado = Value::Nil;
+    executar_registrando_resultado(source, contexto, &mut resultado)?;
+    Ok(resultado)
+}
+
+fn executar_registrando_resultado(
+    source: &str,
+    contexto: &mut Contexto,
+    resultado_final: &mut Value,
+) -> Result<(), InterpreterError> {
+    let (simbolos, erros) = analisar(source);
+    if !erros.is_empty() {
+        return Err(InterpreterError::Lexico(erros));
+    }
+    if contexto.rastreamento {
+        let lista: Vec<_> = simbolos.iter().map(|token| &token.simbolo).collect();
+        let _ = writeln!(contexto.depuracao, "Símbolos encontrados: {:?}", lista);
+    }
+
+    let programa = Analisador::new(simbolos)
+        .analisar_programa()
+        .map_err(InterpreterError::Sintaxe)?;
+
+    // Falhas ao escrever o rastreamento não interrompem o programa
+    for declaracao in &programa {
+        if contexto.rastreamento {
+            let trecho = &source[declaracao.span.inicio..declaracao.span.fim()];
+            let _ = writeln!(contexto.depuracao, "\nDeclaração linha {}: {}", declaracao.span.linha, trecho);
+            let _ = declaracao.imprimir(&mut contexto.depuracao, 0);
+        }
+        match declaracao.executar(contexto) {
+            Ok(Some(valor)) => {
+                *resultado_final = valor;
+                if contexto.rastreamento {
+                    let _ = writeln!(contexto.depuracao, "Resultado da expressão: {}", resultado_final);
+                }
             }
+            Ok(None) => {}
+            Err(Interrupcao::Erro(erro)) => return Err(InterpreterError::Execucao(erro)),
+            // `return` fora de função é rejeitado pelo analisador sintático
+            Err(Interrupcao::Retorno(_)) => break,
         }
     }
-    resultado_final
+    Ok(())
 }
@@ -0,0 +1,104 @@
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ambiente::Ambiente;
+use crate::arvore_sintatica_abstrata::TipoErroExecucao;
+use crate::valor::{FuncaoNativa, Value};
+
+type Implementacao = fn(Vec<Value>) -> Result<Value, TipoErroExecucao>;
+
+// Funções disponíveis em todo programa, como variáveis globais. Como qualquer
+// global, podem ser redefinidas pelo programa.
+const NATIVAS: &[(&str, usize, Implementacao)] = &[
+    ("clock", 0, clock),
+    ("sqrt", 1, sqrt),
+    ("abs", 1, abs),
+    ("floor", 1, floor),
+    ("pow", 2, pow),
+    ("min", 2, min),
+    ("max", 2, max),
+    ("len", 1, len),
+    ("type", 1, tipo),
+    ("str", 1, str),
+];
+
+pub fn registrar(ambiente: &mut Ambiente) {
+    for &(nome, aridade, funcao) in NATIVAS {
+        let nativa = FuncaoNativa { nome: nome.to_string(), aridade, funcao: Box::new(funcao) };
+        ambiente.definir(nome, Value::Nativa(Rc::new(nativa)));
+    }
+}
+
+fn argumento_invalido(funcao: &str, esperado: &'static str, valor: &Value) -> TipoErroExecucao {
+    TipoErroExecucao::ArgumentoInvalido {
+        funcao: funcao.to_string(),
+        esperado,
+        recebido: valor.nome_tipo(),
+    }
+}
+
+fn numero(funcao: &str, valor: &Value) -> Result<f64, TipoErroExecucao> {
+    valor.como_float().ok_or_else(|| argumento_invalido(funcao, "number", valor))
+}
+
+// Segundos desde 1970, com fração, para medir tempo de execução
+fn clock(_: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    let agora = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(Value::Float(agora.as_secs_f64()))
+}
+
+fn sqrt(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    Ok(Value::Float(numero("sqrt", &args[0])?.sqrt()))
+}
+
+fn abs(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    match &args[0] {
+        Value::Inteiro(n) => Ok(n.checked_abs().map_or(Value::Float((*n as f64).abs()), Value::Inteiro)),
+        outro => Ok(Value::Float(numero("abs", outro)?.abs())),
+    }
+}
+
+fn floor(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    match &args[0] {
+        Value::Inteiro(n) => Ok(Value::Inteiro(*n)),
+        outro => Ok(Value::Float(numero("floor", outro)?.floor())),
+    }
+}
+
+fn pow(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    // Inteiro elevado a expoente inteiro não negativo continua inteiro
+    if let (Value::Inteiro(base), Value::Inteiro(expoente)) = (&args[0], &args[1])
+        && let Ok(expoente) = u32::try_from(*expoente)
+        && let Some(n) = base.checked_pow(expoente)
+    {
+        return Ok(Value::Inteiro(n));
+    }
+    Ok(Value::Float(numero("pow", &args[0])?.powf(numero("pow", &args[1])?)))
+}
+
+// min e max devolvem o próprio argumento, preservando inteiros
+fn min(mut args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    let (a, b) = (numero("min", &args[0])?, numero("min", &args[1])?);
+    Ok(args.swap_remove(if b < a { 1 } else { 0 }))
+}
+
+fn max(mut args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    let (a, b) = (numero("max", &args[0])?, numero("max", &args[1])?);
+    Ok(args.swap_remove(if b > a { 1 } else { 0 }))
+}
+
+// Conta caracteres, não bytes: len("ção") é 3
+fn len(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    match &args[0] {
+        Value::String(s) => Ok(Value::Inteiro(s.chars().count() as i64)),
+        outro => Err(argumento_invalido("len", "string", outro)),
+    }
+}
+
+fn tipo(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    Ok(Value::String(args[0].nome_tipo().to_string()))
+}
+
+fn str(args: Vec<Value>) -> Result<Value, TipoErroExecucao> {
+    Ok(Value::String(args[0].to_string()))
+}
@@ -0,0 +1,179 @@
+use std::io::{self, BufRead, Write};
+
+use crate::ambiente::Contexto;
+use crate::analisador_lexico::{Simbolo, TipoLexError, analisar};
+use crate::analisador_sintatico::Analisador;
+use crate::arvore_sintatica_abstrata::{Interrupcao, Stmt, TipoStmt};
+use crate::valor::Value;
+
+const AJUDA: &str = "comandos:
+  :ast <código>     mostra a árvore sintática do código, sem executá-lo
+  :tokens <código>  mostra os símbolos do código
+  :env              mostra as variáveis globais
+  :reset            apaga todas as variáveis
+  :help             mostra esta mensagem
+  :quit             sai (assim como Ctrl-D)";
+
+// Sessão interativa. As variáveis continuam existindo de uma entrada para a
+// outra, e o valor de expressões soltas é mostrado logo abaixo delas.
+pub struct Repl {
+    contexto: Contexto,
+}
+
+impl Repl {
+    // Prompts, resultados e saída do `print` vão para `saida`; mensagens de
+    // erro vão para `diagnosticos`
+    pub fn new(saida: impl Write + 'static, diagnosticos: impl Write + 'static) -> Self {
+        let mut contexto = Contexto::new();
+        contexto.saida = Box::new(saida);
+        contexto.diagnosticos = Box::new(diagnosticos);
+        Repl { contexto }
+    }
+
+    // Lê e executa entradas até o fim de `entrada` ou até o comando `:quit`
+    pub fn executar(&mut self, entrada: impl BufRead) -> io::Result<()> {
+        let mut linhas = entrada.lines();
+        loop {
+            write!(self.contexto.saida, "> ")?;
+            self.contexto.saida.flush()?;
+            let Some(linha) = linhas.next() else { break };
+            let mut texto = linha?;
+
+            // Enquanto houver chaves abertas, a entrada continua na próxima linha
+            while entrada_incompleta(&texto) {
+                write!(self.contexto.saida, "... ")?;
+                self.contexto.saida.flush()?;
+                match linhas.next() {
+                    Some(linha) => {
+                        texto.push('\n');
+                        texto.push_str(&linha?);
+                    }
+                    None => break,
+                }
+            }
+
+            if !self.processar(&texto)? {
+                return Ok(());
+            }
+        }
+        // Termina a linha do último prompt
+        writeln!(self.contexto.saida)
+    }
+
+    // Trata uma entrada completa; devolve false quando a sessão deve terminar
+    pub fn processar(&mut self, texto: &str) -> io::Result<bool> {
+        let texto = texto.trim();
+        let (comando, argumento) = match texto.split_once(char::is_whitespace) {
+            Some((comando, argumento)) => (comando, argumento.trim()),
+            None => (texto, ""),
+        };
+        match comando {
+            ":quit" | ":q" => return Ok(false),
+            ":help" => writeln!(self.contexto.saida, "{}", AJUDA)?,
+            ":ast" => self.mostrar_arvore(argumento)?,
+            ":tokens" => self.mostrar_simbolos(argumento)?,
+            ":env" => self.mostrar_ambiente()?,
+            ":reset" => self.contexto.reiniciar(),
+            _ if comando.starts_with(':') => {
+                writeln!(self.contexto.diagnosticos, "comando desconhecido {} (veja :help)", comando)?;
+            }
+            _ => self.executar_codigo(texto)?,
+        }
+        Ok(true)
+    }
+
+    fn executar_codigo(&mut self, codigo: &str) -> io::Result<()> {
+        let Some(programa) = self.analisar(codigo)? else {
+            return Ok(());
+        };
+        for declaracao in &programa {
+            match declaracao.executar(&mut self.contexto) {
+                // Só expressões soltas mostram o valor; `var x = 1` ou um
+                // `print` não mostram nada além da própria saída
+                Ok(Some(valor)) if matches!(declaracao.tipo, TipoStmt::Expressao(_)) => {
+                    writeln!(self.contexto.saida, "{}", valor)?;
+                }
+                Ok(_) => {}
+                Err(Interrupcao::Erro(erro)) => {
+                    write!(self.contexto.diagnosticos, "{}", erro.renderizar(codigo))?;
+                    break;
+                }
+                Err(Interrupcao::Retorno(_)) => break,
+            }
+        }
+        Ok(())
+    }
+
+    // Analisa o código, mostrando os erros léxicos e sintáticos, se houver
+    fn analisar(&mut self, codigo: &str) -> io::Result<Option<Vec<Stmt>>> {
+        let (simbolos, erros) = analisar(codigo);
+        if !erros.is_empty() {
+            for erro in &erros {
+                write!(self.contexto.diagnosticos, "{}", erro.renderizar(codigo))?;
+            }
+            return Ok(None);
+        }
+        match Analisador::new(simbolos).analisar_programa() {
+            Ok(programa) => Ok(Some(programa)),
+            Err(erros) => {
+                for erro in &erros {
+                    write!(self.contexto.diagnosticos, "{}", erro.renderizar(codigo))?;
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn mostrar_arvore(&mut self, codigo: &str) -> io::Result<()> {
+        if let Some(programa) = self.analisar(codigo)? {
+            for declaracao in &programa {
+                declaracao.imprimir(&mut self.contexto.saida, 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn mostrar_simbolos(&mut self, codigo: &str) -> io::Result<()> {
+        let (simbolos, erros) = analisar(codigo);
+        for token in &simbolos {
+            writeln!(self.contexto.saida, "{}:{}\t{}", token.span.linha, token.span.coluna, token.simbolo)?;
+        }
+        for erro in &erros {
+            write!(self.contexto.diagnosticos, "{}", erro.renderizar(codigo))?;
+        }
+        Ok(())
+    }
+
+    // As funções nativas ficam de fora para não poluir a listagem
+    fn mostrar_ambiente(&mut self) -> io::Result<()> {
+        let globais = self.contexto.globais.borrow();
+        for nome in globais.nomes() {
+            match globais.obter(&nome) {
+                Some(Value::Nativa(_)) | None => {}
+                Some(valor) => writeln!(self.contexto.saida, "{} = {}", nome, valor)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Uma entrada está incompleta quando abre mais chaves do que fecha ou quando
+// termina no meio de um comentário de bloco ou de uma string
+fn entrada_incompleta(texto: &str) -> bool {
+    if texto.trim_start().starts_with(':') {
+        return false;
+    }
+    let (simbolos, erros) = analisar(texto);
+    let mut abertas = 0i64;
+    for token in &simbolos {
+        match token.simbolo {
+            Simbolo::AbreChaves => abertas += 1,
+            Simbolo::FechaChaves => abertas -= 1,
+            _ => {}
+        }
+    }
+    abertas > 0
+        || erros.iter().any(|erro| {
+            matches!(erro.tipo, TipoLexError::ComentarioNaoTerminado | TipoLexError::StringNaoTerminada)
+        })
+}
@@ -0,0 +1,36 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+// Destino de escrita que guarda tudo em memória. Os clones compartilham o
+// mesmo buffer, então um clone pode ser entregue ao interpretador enquanto
+// outro é usado para ler o que foi escrito (por exemplo, em testes).
+#[derive(Clone, Default)]
+pub struct Captura {
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Captura {
+    pub fn new() -> Self {
+        Captura::default()
+    }
+
+    pub fn conteudo(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.borrow()).into_owned()
+    }
+
+    pub fn limpar(&self) {
+        self.buffer.borrow_mut().clear();
+    }
+}
+
+impl Write for Captura {
+    fn write(&mut self, dados: &[u8]) -> io::Result<usize> {
+        self.buffer.borrow_mut().extend_from_slice(dados);
+        Ok(dados.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
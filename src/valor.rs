@@ -0,0 +1,241 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ambiente::{Ambiente, Contexto};
+use crate::arvore_sintatica_abstrata::{DeclaracaoFuncao, TipoErroExecucao};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Booleano(bool),
+    Inteiro(i64),
+    Float(f64),
+    String(String),
+    Funcao(Rc<Funcao>),
+    Nativa(Rc<FuncaoNativa>),
+    Classe(Rc<Classe>),
+    Instancia(Rc<RefCell<Instancia>>),
+}
+
+// Função definida pelo usuário com `fun`. Guarda o ambiente em que foi
+// declarada, para enxergar as variáveis de lá mesmo depois que o escopo
+// original terminou (closure).
+pub struct Funcao {
+    pub declaracao: Rc<DeclaracaoFuncao>,
+    pub closure: Rc<RefCell<Ambiente>>,
+}
+
+impl Funcao {
+    pub fn aridade(&self) -> usize {
+        self.declaracao.parametros.len()
+    }
+
+    // Cria uma cópia do método em que `this` se refere a `instancia`
+    pub fn vincular(&self, instancia: Value, ctx: &mut Contexto) -> Funcao {
+        let mut ambiente = Ambiente::com_pai(Rc::clone(&self.closure));
+        ambiente.definir("this", instancia);
+        Funcao {
+            declaracao: Rc::clone(&self.declaracao),
+            closure: ctx.capturar(Rc::new(RefCell::new(ambiente))),
+        }
+    }
+}
+
+// Duas funções só são iguais se forem o mesmo valor
+impl PartialEq for Funcao {
+    fn eq(&self, outra: &Funcao) -> bool {
+        std::ptr::eq(self, outra)
+    }
+}
+
+impl fmt::Debug for Funcao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.declaracao.nome)
+    }
+}
+
+// Função implementada em Rust (veja o módulo `nativas`). Recebe os argumentos
+// já avaliados, e o número deles já foi conferido com `aridade`.
+pub struct FuncaoNativa {
+    pub nome: String,
+    pub aridade: usize,
+    pub funcao: Box<dyn Fn(Vec<Value>) -> Result<Value, TipoErroExecucao>>,
+}
+
+impl PartialEq for FuncaoNativa {
+    fn eq(&self, outra: &FuncaoNativa) -> bool {
+        std::ptr::eq(self, outra)
+    }
+}
+
+impl fmt::Debug for FuncaoNativa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.nome)
+    }
+}
+
+// Classe declarada com `class`. Chamar a classe cria uma instância e, se
+// existir, executa o método `init` com os argumentos da chamada.
+pub struct Classe {
+    pub nome: String,
+    pub superclasse: Option<Rc<Classe>>,
+    pub metodos: HashMap<String, Rc<Funcao>>,
+}
+
+impl Classe {
+    // Procura o método na própria classe e depois nas superclasses
+    pub fn metodo(&self, nome: &str) -> Option<Rc<Funcao>> {
+        match self.metodos.get(nome) {
+            Some(metodo) => Some(Rc::clone(metodo)),
+            None => self.superclasse.as_ref()?.metodo(nome),
+        }
+    }
+
+    pub fn aridade(&self) -> usize {
+        self.metodo("init").map_or(0, |init| init.aridade())
+    }
+}
+
+impl PartialEq for Classe {
+    fn eq(&self, outra: &Classe) -> bool {
+        std::ptr::eq(self, outra)
+    }
+}
+
+impl fmt::Debug for Classe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<class {}>", self.nome)
+    }
+}
+
+pub struct Instancia {
+    pub classe: Rc<Classe>,
+    pub campos: HashMap<String, Value>,
+}
+
+impl Instancia {
+    pub fn new(classe: Rc<Classe>) -> Self {
+        Instancia { classe, campos: HashMap::new() }
+    }
+}
+
+impl PartialEq for Instancia {
+    fn eq(&self, outra: &Instancia) -> bool {
+        std::ptr::eq(self, outra)
+    }
+}
+
+// Não mostra os campos: uma instância pode guardar a si mesma
+impl fmt::Debug for Instancia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} instance>", self.classe.nome)
+    }
+}
+
+impl Value {
+    // Apenas nil, false e zero são considerados falsos
+    pub fn eh_verdadeiro(&self) -> bool {
+        match self {
+            Value::Nil => false,
+            Value::Booleano(b) => *b,
+            Value::Inteiro(n) => *n != 0,
+            Value::Float(n) => *n != 0.0,
+            Value::String(_) => true,
+            Value::Funcao(_) | Value::Nativa(_) | Value::Classe(_) | Value::Instancia(_) => true,
+        }
+    }
+
+    pub fn nome_tipo(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Booleano(_) => "bool",
+            Value::Inteiro(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Funcao(_) | Value::Nativa(_) => "function",
+            Value::Classe(_) => "class",
+            Value::Instancia(_) => "instance",
+        }
+    }
+
+    // Converte para f64 quando o valor é numérico (inteiro ou float)
+    pub fn como_float(&self) -> Option<f64> {
+        match self {
+            Value::Inteiro(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    // Igualdade da linguagem: inteiros e floats são comparados numericamente
+    pub fn igual(&self, outro: &Value) -> bool {
+        match (self, outro) {
+            (Value::Inteiro(a), Value::Float(b)) | (Value::Float(b), Value::Inteiro(a)) => {
+                *a as f64 == *b
+            }
+            _ => self == outro,
+        }
+    }
+}
+
+// Conversões usadas por quem embute o interpretador (veja `Interpreter`)
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Inteiro(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Booleano(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Nil
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Booleano(b) => write!(f, "{}", b),
+            Value::Inteiro(n) => write!(f, "{}", n),
+            Value::Float(n) => {
+                // Mostra "4.0" em vez de "4" para diferenciar de inteiros
+                if n.fract() == 0.0 && n.is_finite() {
+                    write!(f, "{:.1}", n)
+                } else {
+                    write!(f, "{}", n)
+                }
+            }
+            Value::String(s) => write!(f, "{}", s),
+            Value::Funcao(funcao) => write!(f, "{:?}", funcao),
+            Value::Nativa(funcao) => write!(f, "{:?}", funcao),
+            Value::Classe(classe) => write!(f, "{:?}", classe),
+            Value::Instancia(instancia) => write!(f, "{:?}", instancia.borrow()),
+        }
+    }
+}
@@ -1,83 +1,934 @@
-use mini_lox_rust::executar;
+use std::rc::Rc;
+
+use mini_lox_rust::analisador_lexico::{
+    LexError, ParteString, Simbolo, Span, TipoLexError, analisar, analisar_com_comentarios,
+};
+use mini_lox_rust::analisador_sintatico::{Analisador, ParseError};
+use mini_lox_rust::arvore_sintatica_abstrata::{Interrupcao, RuntimeError, TipoErroExecucao};
+use mini_lox_rust::ambiente::Contexto;
+use mini_lox_rust::{Interpreter, InterpreterError, executar, executar_com_contexto};
+use mini_lox_rust::repl::Repl;
+use mini_lox_rust::saida::Captura;
+use mini_lox_rust::valor::Value;
+
+fn avaliar(codigo: &str) -> Result<Value, RuntimeError> {
+    let mut analisador = Analisador::new(analisar(codigo).0);
+    let ast = analisador.analisar_expressao().expect("erro de sintaxe");
+    ast.avaliar_com_contexto(&mut Contexto::new())
+}
+
+fn tipo_erro(codigo: &str) -> Option<TipoErroExecucao> {
+    avaliar(codigo).err().map(|erro| erro.tipo)
+}
+
+fn tipo_erro_programa(codigo: &str) -> Option<TipoErroExecucao> {
+    let programa = Analisador::new(analisar(codigo).0).analisar_programa().expect("erro de sintaxe");
+    let mut contexto = Contexto::new();
+    for declaracao in &programa {
+        if let Err(Interrupcao::Erro(erro)) = declaracao.executar(&mut contexto) {
+            return Some(erro.tipo);
+        }
+    }
+    None
+}
 
 #[test]
 fn test_soma_simples() {
     let resultado = executar("2 + 3");
-    assert_eq!(resultado, 5.0);
+    assert_eq!(resultado, Value::Inteiro(5));
 }
 
 #[test]
 fn test_operacoes_aritmeticas() {
-    assert_eq!(executar("5 * (2 + 3)"), 25.0);
-    assert_eq!(executar("10 / 2 - 1"), 4.0);
+    assert_eq!(executar("5 * (2 + 3)"), Value::Inteiro(25));
+    assert_eq!(executar("10 / 2 - 1"), Value::Float(4.0));
+    assert_eq!(executar("7 / 2"), Value::Float(3.5));
+    assert_eq!(executar("1.5 + 1"), Value::Float(2.5));
 }
 
 #[test]
 fn test_numeros_validos() {
-    assert_eq!(executar("1"), 1.0);
-    assert_eq!(executar("2.72"), 2.72);
-    assert_eq!(executar("3"), 3.0);
-    assert_eq!(executar("42"), 42.0);
-    assert_eq!(executar("5"), 5.0);
+    assert_eq!(executar("1"), Value::Inteiro(1));
+    assert_eq!(executar("2.72"), Value::Float(2.72));
+    assert_eq!(executar("3"), Value::Inteiro(3));
+    assert_eq!(executar("42"), Value::Inteiro(42));
+    assert_eq!(executar("5"), Value::Inteiro(5));
+}
+
+#[test]
+fn test_valores_dinamicos() {
+    assert_eq!(executar("\"Hello World\""), Value::String("Hello World".to_string()));
+    assert_eq!(executar("var c\nc"), Value::Nil);
+    assert_eq!(executar("true"), Value::Booleano(true));
+    assert_eq!(executar("1 == 1.0"), Value::Booleano(true));
+
+    assert_eq!(Value::Nil.to_string(), "nil");
+    assert_eq!(Value::Booleano(true).to_string(), "true");
+    assert_eq!(Value::Float(4.0).to_string(), "4.0");
+    assert_eq!(Value::String("texto".to_string()).to_string(), "texto");
 }
 
 #[test]
 fn test_operadores_unarios() {
-    assert_eq!(executar("-5"), -5.0);
-    assert_eq!(executar("!true"), 0.0); // !true -> false -> 0.0
-    assert_eq!(executar("!false"), 1.0); // !false -> true -> 1.0
-    assert_eq!(executar("!!true"), 1.0);
+    assert_eq!(executar("-5"), Value::Inteiro(-5));
+    assert_eq!(executar("!true"), Value::Booleano(false));
+    assert_eq!(executar("!false"), Value::Booleano(true));
+    assert_eq!(executar("!!true"), Value::Booleano(true));
 }
 
 #[test]
 fn test_operadores_logicos() {
-    assert_eq!(executar("true and false"), 0.0);
-    assert_eq!(executar("true or false"), 1.0);
-    assert_eq!(executar("false or true"), 1.0);
-    assert_eq!(executar("false and true"), 0.0);
+    assert_eq!(executar("true and false"), Value::Booleano(false));
+    assert_eq!(executar("true or false"), Value::Booleano(true));
+    assert_eq!(executar("false or true"), Value::Booleano(true));
+    assert_eq!(executar("false and true"), Value::Booleano(false));
 }
 
 #[test]
 fn test_atribuicao_de_variaveis() {
     let codigo = "var x = 2\nx = 4\nx";
-    assert_eq!(executar(codigo), 4.0);
+    assert_eq!(executar(codigo), Value::Inteiro(4));
 }
 
 #[test]
 fn test_declaracao_de_variaveis() {
     let codigo_com_valor = "var a = 10\na";
-    assert_eq!(executar(codigo_com_valor), 10.0);
+    assert_eq!(executar(codigo_com_valor), Value::Inteiro(10));
 
     let codigo_multiplo = "var c = 1\nvar d = c + 2\nd";
-    assert_eq!(executar(codigo_multiplo), 3.0);
+    assert_eq!(executar(codigo_multiplo), Value::Inteiro(3));
 }
 
 #[test]
 fn test_blocos() {
     let codigo = "{ var foo = 1; var bar = 2; bar }";
-    assert_eq!(executar(codigo), 2.0);
+    assert_eq!(executar(codigo), Value::Inteiro(2));
 
+    // Variáveis declaradas no bloco não existem fora dele
     let codigo_fora = "{ var a = 1; } a";
-    assert_eq!(executar(codigo_fora), 1.0);
+    assert_eq!(executar(codigo_fora), Value::Nil);
+    assert_eq!(
+        tipo_erro_programa(codigo_fora),
+        Some(TipoErroExecucao::VariavelIndefinida("a".to_string()))
+    );
+
+    // Sombreamento: o bloco declara outra variável com o mesmo nome
+    let codigo_sombra = "var a = 1\n{ var a = 2; a = a + 10; }\na";
+    assert_eq!(executar(codigo_sombra), Value::Inteiro(1));
+
+    // Atribuição procura a variável nos escopos externos
+    let codigo_externo = "var a = 1\n{ { a = 5; } }\na";
+    assert_eq!(executar(codigo_externo), Value::Inteiro(5));
+
+    // Atribuir a uma variável não declarada é um erro
+    assert_eq!(
+        tipo_erro_programa("b = 1"),
+        Some(TipoErroExecucao::VariavelIndefinida("b".to_string()))
+    );
+
+    // Modo de compatibilidade em que as variáveis vazam do bloco
+    let mut contexto = Contexto::new();
+    contexto.blocos_compartilham_escopo = true;
+    assert_eq!(executar_com_contexto(codigo_fora, &mut contexto), Value::Inteiro(1));
 }
 
 #[test]
 fn test_if_else() {
     let codigo_if = "if (true) 10";
-    assert_eq!(executar(codigo_if), 10.0);
+    assert_eq!(executar(codigo_if), Value::Inteiro(10));
 
     let codigo_if_false = "if (false) 10";
-    assert_eq!(executar(codigo_if_false), 0.0);
+    assert_eq!(executar(codigo_if_false), Value::Nil);
 
     let codigo_if_else_true = "if (1 < 2) 3 else 4";
-    assert_eq!(executar(codigo_if_else_true), 3.0);
+    assert_eq!(executar(codigo_if_else_true), Value::Inteiro(3));
 
     let codigo_if_else_false = "if (1 > 2) 3 else 4";
-    assert_eq!(executar(codigo_if_else_false), 4.0);
+    assert_eq!(executar(codigo_if_else_false), Value::Inteiro(4));
 }
 
 #[test]
 fn test_while() {
     let codigo = "var i = 0\nwhile (i < 5) { i = i + 1; }\ni";
-    assert_eq!(executar(codigo), 5.0);
-} 
\ No newline at end of file
+    assert_eq!(executar(codigo), Value::Inteiro(5));
+}
+
+#[test]
+fn test_erros_de_execucao() {
+    assert_eq!(
+        tipo_erro("nao_existe + 1"),
+        Some(TipoErroExecucao::VariavelIndefinida("nao_existe".to_string()))
+    );
+    assert_eq!(tipo_erro("1 / 0"), Some(TipoErroExecucao::DivisaoPorZero));
+    assert_eq!(tipo_erro("5 % 0"), Some(TipoErroExecucao::DivisaoPorZero));
+    assert_eq!(
+        tipo_erro("\"a\" - 1"),
+        Some(TipoErroExecucao::OperandosInvalidos { operador: "-", esquerda: "string", direita: "int" })
+    );
+    assert_eq!(tipo_erro("10()"), Some(TipoErroExecucao::NaoChamavel("int")));
+    assert_eq!(
+        tipo_erro("true.campo"),
+        Some(TipoErroExecucao::AtributoDesconhecido { tipo: "bool", nome: "campo".to_string() })
+    );
+
+    // O erro interrompe o restante do programa
+    assert_eq!(executar("var x = 1\nx\nx = x / 0\nx = 5"), Value::Inteiro(1));
+}
+
+#[test]
+fn test_spans() {
+    let simbolos = analisar("var nome =\n  \"olá\"").0;
+    let spans: Vec<_> = simbolos.iter().map(|token| (token.simbolo.clone(), token.span)).collect();
+    assert_eq!(spans[0], (Simbolo::Var, Span { inicio: 0, linha: 1, coluna: 1, tamanho: 3 }));
+    assert_eq!(
+        spans[1],
+        (Simbolo::Identificador("nome".to_string()), Span { inicio: 4, linha: 1, coluna: 5, tamanho: 4 })
+    );
+    assert_eq!(
+        spans[3],
+        (Simbolo::String("olá".to_string()), Span { inicio: 13, linha: 2, coluna: 3, tamanho: 6 })
+    );
+    assert_eq!(spans[4].0, Simbolo::Fim);
+
+    let ast = Analisador::new(analisar("1 + (2 * x)").0).analisar_expressao().unwrap();
+    assert_eq!(ast.span, Span { inicio: 0, linha: 1, coluna: 1, tamanho: 11 });
+
+    // O erro aponta para o nó que falhou
+    let erro = avaliar("1 + (2 * x)").unwrap_err();
+    assert_eq!(erro.span, Span { inicio: 9, linha: 1, coluna: 10, tamanho: 1 });
+    let erro = avaliar("10 + 8 / 0").unwrap_err();
+    assert_eq!(erro.span, Span { inicio: 5, linha: 1, coluna: 6, tamanho: 5 });
+}
+
+#[test]
+fn test_erros_de_sintaxe() {
+    let erro = Analisador::new(analisar("if (x 10").0).analisar_programa().unwrap_err().remove(0);
+    assert_eq!(
+        erro,
+        ParseError {
+            esperado: vec!["')'".to_string()],
+            encontrado: Simbolo::NumeroInteiro(10),
+            span: Span { inicio: 6, linha: 1, coluna: 7, tamanho: 2 },
+        }
+    );
+    assert_eq!(erro.to_string(), "esperado ')', encontrado número 10");
+    assert_eq!(
+        erro.renderizar("if (x 10"),
+        concat!(
+            "erro: esperado ')', encontrado número 10\n",
+            " --> linha 1, coluna 7\n",
+            "  |\n",
+            "1 | if (x 10\n",
+            "  |       ^^\n",
+        )
+    );
+
+    let erro = Analisador::new(analisar("var = 3").0).analisar_programa().unwrap_err().remove(0);
+    assert_eq!(erro.esperado, vec!["identificador".to_string()]);
+    assert_eq!(erro.encontrado, Simbolo::Igual);
+
+    let erro = Analisador::new(analisar("f(1, 2").0).analisar_expressao().unwrap_err();
+    assert_eq!(erro.to_string(), "esperado um de ',', ')', encontrado fim da entrada");
+}
+
+#[test]
+fn test_erros_lexicos() {
+    let (simbolos, erros) = analisar("1 @ 2\r\n");
+    let lista: Vec<_> = simbolos.into_iter().map(|token| token.simbolo).collect();
+    assert_eq!(lista, vec![Simbolo::NumeroInteiro(1), Simbolo::NumeroInteiro(2), Simbolo::Fim]);
+    assert_eq!(
+        erros,
+        vec![LexError {
+            tipo: TipoLexError::CaractereInvalido('@'),
+            span: Span { inicio: 2, linha: 1, coluna: 3, tamanho: 1 },
+        }]
+    );
+
+    let (_, erros) = analisar("\"sem fim");
+    assert_eq!(erros[0].tipo, TipoLexError::StringNaoTerminada);
+    assert_eq!(erros[0].span.tamanho, 8);
+
+    let (simbolos, erros) = analisar("99999999999999999999");
+    assert_eq!(
+        erros[0].tipo,
+        TipoLexError::InteiroMuitoGrande("99999999999999999999".to_string())
+    );
+    assert_eq!(simbolos[0].simbolo, Simbolo::NumeroFloat(1e20));
+
+    // Com erros léxicos o programa não é executado
+    assert_eq!(executar("var x = 1\nx = 2 $\nx"), Value::Nil);
+}
+
+#[test]
+fn test_programa_inteiro() {
+    let codigo = "var a = 48
+var b = 18
+while (b != 0) {
+    var temp = b
+    b = a % b
+    a = temp
+}
+a";
+    assert_eq!(executar(codigo), Value::Inteiro(6));
+
+    // Um '-' ou '(' no início da linha começa uma nova expressão
+    assert_eq!(executar("!false\n-5"), Value::Inteiro(-5));
+    assert_eq!(executar("var f = 1\n(2)"), Value::Inteiro(2));
+    assert_eq!(executar("1 +\n  2"), Value::Inteiro(3));
+    assert_eq!(executar("if (false) 1\nelse 2"), Value::Inteiro(2));
+
+    let erros = Analisador::new(analisar("1 2\nvar = 3\nx").0).analisar_programa().unwrap_err();
+    assert_eq!(erros.len(), 2);
+    assert_eq!(erros[0].to_string(), "esperado um de ';', quebra de linha, encontrado número 2");
+    assert_eq!(erros[1].span.linha, 2);
+}
+
+#[test]
+fn test_comentarios() {
+    let codigo = "// comentário de linha\nvar x = 10 / 2 // fim\n/* bloco /* aninhado */ ainda */ x";
+    assert_eq!(executar(codigo), Value::Float(5.0));
+
+    let (simbolos, erros) = analisar_com_comentarios("1 /* a /* b */ */ // c");
+    assert!(erros.is_empty());
+    let lista: Vec<_> = simbolos.into_iter().map(|token| token.simbolo).collect();
+    assert_eq!(
+        lista,
+        vec![
+            Simbolo::NumeroInteiro(1),
+            Simbolo::Comentario("/* a /* b */ */".to_string()),
+            Simbolo::Comentario("// c".to_string()),
+            Simbolo::Fim,
+        ]
+    );
+
+    let (_, erros) = analisar("/* /* */ 1");
+    assert_eq!(erros[0].tipo, TipoLexError::ComentarioNaoTerminado);
+}
+
+#[test]
+fn test_declaracoes_separadas_de_expressoes() {
+    // Declarações não produzem valor: o resultado é o da última expressão
+    assert_eq!(executar("1\nprint 2"), Value::Inteiro(1));
+    assert_eq!(executar("var x = 3"), Value::Nil);
+    assert_eq!(executar("var i = 0\nwhile (i < 3) i = i + 1"), Value::Inteiro(3));
+
+    // `var`, `if` e blocos não são aceitos no meio de expressões
+    let erros = Analisador::new(analisar("1 + (var x = 1)").0).analisar_programa().unwrap_err();
+    assert_eq!(erros[0].encontrado, Simbolo::Var);
+    let erro = Analisador::new(analisar("if (true) 1").0).analisar_expressao().unwrap_err();
+    assert_eq!(erro.encontrado, Simbolo::If);
+}
+
+#[test]
+fn test_funcoes() {
+    let codigo = "fun gcd(a, b) {
+    while (b != 0) {
+        var temp = b
+        b = a % b
+        a = temp
+    }
+    return a
+}
+gcd(48, 18)";
+    assert_eq!(executar(codigo), Value::Inteiro(6));
+
+    let recursiva = "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }\nfib(15)";
+    assert_eq!(executar(recursiva), Value::Inteiro(610));
+
+    // Sem return, a função devolve nil
+    assert_eq!(executar("fun nada() { 1 + 1; }\nnada()"), Value::Nil);
+    assert_eq!(executar("fun f() {}\nf").to_string(), "<fn f>");
+    assert_eq!(executar("fun f() {}\nf == f"), Value::Booleano(true));
+
+    // Cada chamada tem seu próprio ambiente
+    assert_eq!(
+        tipo_erro_programa("fun f() { var local = 1; }\nf()\nlocal"),
+        Some(TipoErroExecucao::VariavelIndefinida("local".to_string()))
+    );
+    assert_eq!(
+        tipo_erro_programa("fun soma(a, b) { return a + b; }\nsoma(1)"),
+        Some(TipoErroExecucao::NumeroDeArgumentos { esperado: 2, recebido: 1 })
+    );
+
+    // O limite padrão é verificado na própria thread do teste (2 MB de pilha)
+    let profunda = "fun c(n) { if (n == 0) return 0; return 1 + c(n - 1); }\nc(100)";
+    assert_eq!(executar(profunda), Value::Inteiro(100));
+    assert_eq!(
+        tipo_erro_programa("fun infinita(n) { return infinita(n + 1); }\ninfinita(0)"),
+        Some(TipoErroExecucao::EstouroDePilha(400))
+    );
+    // Blocos e expressões aninhadas também contam para o limite
+    let aninhada = "fun c(n) { { { { return 1 + (1 + (1 + (1 + (1 + (1 + c(n - 1)))))); } } } }\nc(1000000)";
+    assert_eq!(tipo_erro_programa(aninhada), Some(TipoErroExecucao::EstouroDePilha(400)));
+    let metodo = "class A { f(n) { return \"${this.f(n + 1)}\"; } }\nA().f(0)";
+    assert_eq!(tipo_erro_programa(metodo), Some(TipoErroExecucao::EstouroDePilha(400)));
+
+    // O limite pode ser ajustado no contexto
+    let mut contexto = Contexto::new();
+    contexto.profundidade_maxima = 10;
+    let programa = Analisador::new(analisar("fun f(n) { return f(n + 1); }\nf(0)").0).analisar_programa().unwrap();
+    let erro = programa.iter().find_map(|declaracao| declaracao.executar(&mut contexto).err());
+    assert!(matches!(erro, Some(Interrupcao::Erro(erro)) if erro.tipo == TipoErroExecucao::EstouroDePilha(10)));
+
+    let erros = Analisador::new(analisar("return 1").0).analisar_programa().unwrap_err();
+    assert_eq!(erros[0].to_string(), "'return' inesperado");
+}
+
+#[test]
+fn test_closures() {
+    let codigo = "fun create_incrementer(n) {
+    fun incrementer(x) { return x + n; }
+    return incrementer;
+}
+create_incrementer(2)(40)";
+    assert_eq!(executar(codigo), Value::Inteiro(42));
+
+    // O contador mantém o estado capturado entre as chamadas
+    let contador = "fun criar_contador() {
+    var i = 0
+    fun contar() {
+        i = i + 1
+        return i
+    }
+    return contar
+}
+var c1 = criar_contador()
+var c2 = criar_contador()
+c1()
+c1()
+c2()
+c1()";
+    assert_eq!(executar(contador), Value::Inteiro(3));
+
+    // Funções de ordem superior
+    let composicao = "fun compor(f, g) { fun h(x) { return f(g(x)); } return h; }
+fun dobro(x) { return x * 2; }
+fun inc(x) { return x + 1; }
+compor(dobro, inc)(5)";
+    assert_eq!(executar(composicao), Value::Inteiro(12));
+}
+
+#[test]
+fn test_lambdas() {
+    let codigo = "fun aplicar(f, x) { return f(x); }
+aplicar(fun (x) { return x * 2; }, 21)";
+    assert_eq!(executar(codigo), Value::Inteiro(42));
+
+    assert_eq!(executar("var dobro = (x) => x * 2\ndobro(4)"), Value::Inteiro(8));
+    assert_eq!(executar("var soma = (a, b) => a + b; soma(1, 2)"), Value::Inteiro(3));
+    assert_eq!(executar("var f = () => { return 7; }\nf()"), Value::Inteiro(7));
+    assert_eq!(executar("(x => x + 1)(1)"), Value::Inteiro(2));
+
+    // Lambdas também capturam o ambiente em que foram criadas
+    let captura = "fun somador(n) { return x => x + n; }
+somador(10)(5)";
+    assert_eq!(executar(captura), Value::Inteiro(15));
+
+    // Parênteses comuns continuam sendo agrupamento
+    assert_eq!(executar("var x = 2\n(x) * 3"), Value::Inteiro(6));
+}
+
+#[test]
+fn test_classes() {
+    let codigo = "class Ponto {
+    init(x, y) {
+        this.x = x
+        this.y = y
+    }
+    soma() { return this.x + this.y; }
+}
+var p = Ponto(1, 2)
+p.x = 10
+p.soma()";
+    assert_eq!(executar(codigo), Value::Inteiro(12));
+
+    // Campos podem ser criados depois, e métodos lembram a instância
+    let vinculado = "class Contador { inc() { this.n = this.n + 1; return this.n; } }
+var c = Contador()
+c.n = 0
+var inc = c.inc
+inc()
+inc()";
+    assert_eq!(executar(vinculado), Value::Inteiro(2));
+
+    assert_eq!(executar("class A {}\nA").to_string(), "<class A>");
+    assert_eq!(executar("class A {}\nA()").to_string(), "<A instance>");
+    assert_eq!(executar("class A {}\nvar a = A()\na == a"), Value::Booleano(true));
+    assert_eq!(executar("class A {}\nA() == A()"), Value::Booleano(false));
+    // Operadores unários se aplicam ao acesso inteiro, não só ao objeto
+    assert_eq!(executar("class A {}\nvar a = A()\na.ativo = false\n!a.ativo"), Value::Booleano(true));
+    assert_eq!(executar("fun f(x) { return x; }\n-f(2)"), Value::Inteiro(-2));
+
+    assert_eq!(
+        tipo_erro_programa("class A {}\nA().campo"),
+        Some(TipoErroExecucao::AtributoIndefinido { classe: "A".to_string(), nome: "campo".to_string() })
+    );
+    assert_eq!(tipo_erro_programa("var n = 1\nn.campo = 2"), Some(TipoErroExecucao::SemCampos("int")));
+    assert_eq!(
+        tipo_erro_programa("class A { init(x) {} }\nA()"),
+        Some(TipoErroExecucao::NumeroDeArgumentos { esperado: 1, recebido: 0 })
+    );
+    assert_eq!(
+        tipo_erro_programa("class A {}\nA(1)"),
+        Some(TipoErroExecucao::NumeroDeArgumentos { esperado: 0, recebido: 1 })
+    );
+
+    let erros = Analisador::new(analisar("this.x").0).analisar_programa().unwrap_err();
+    assert_eq!(erros[0].to_string(), "'this' inesperado");
+}
+
+#[test]
+fn test_heranca() {
+    let codigo = "class Animal {
+    init(nome) { this.nome = nome; }
+    som() { return 1; }
+    descricao() { return this.som() * 10; }
+}
+class Cachorro < Animal {
+    som() { return super.som() + 1; }
+}
+var c = Cachorro(\"rex\")
+c.descricao()";
+    assert_eq!(executar(codigo), Value::Inteiro(20));
+
+    // init e outros métodos são herdados pela cadeia inteira
+    let cadeia = "class A { init(x) { this.x = x; } valor() { return this.x; } }
+class B < A {}
+class C < B { valor() { return super.valor() * 2; } }
+C(21).valor()";
+    assert_eq!(executar(cadeia), Value::Inteiro(42));
+
+    assert_eq!(
+        tipo_erro_programa("var A = 1\nclass B < A {}"),
+        Some(TipoErroExecucao::SuperclasseInvalida("int"))
+    );
+    assert_eq!(
+        tipo_erro_programa("class A {}\nclass B < A { f() { return super.g(); } }\nB().f()"),
+        Some(TipoErroExecucao::AtributoIndefinido { classe: "A".to_string(), nome: "g".to_string() })
+    );
+
+    let erros = Analisador::new(analisar("class A { f() { return super.f(); } }").0)
+        .analisar_programa()
+        .unwrap_err();
+    assert_eq!(erros[0].to_string(), "'super' inesperado");
+}
+
+#[test]
+fn test_funcoes_nativas() {
+    assert_eq!(executar("sqrt(9)"), Value::Float(3.0));
+    assert_eq!(executar("abs(-3)"), Value::Inteiro(3));
+    assert_eq!(executar("pow(2, 10)"), Value::Inteiro(1024));
+    assert_eq!(executar("pow(2, -1)"), Value::Float(0.5));
+    assert_eq!(executar("max(1, 2.5)"), Value::Float(2.5));
+    assert_eq!(executar("min(1, 2.5)"), Value::Inteiro(1));
+    assert_eq!(executar("len(\"ação\")"), Value::Inteiro(4));
+    assert_eq!(executar("type(1.5)"), Value::String("float".to_string()));
+    assert_eq!(executar("str(2)"), Value::String("2".to_string()));
+    assert_eq!(executar("clock() > 0"), Value::Booleano(true));
+    assert_eq!(executar("sqrt").to_string(), "<native fn sqrt>");
+
+    // Nativas são globais comuns e podem ser redefinidas
+    assert_eq!(executar("fun sqrt(x) { return x; }\nsqrt(9)"), Value::Inteiro(9));
+
+    assert_eq!(
+        tipo_erro_programa("sqrt(1, 2)"),
+        Some(TipoErroExecucao::NumeroDeArgumentos { esperado: 1, recebido: 2 })
+    );
+    assert_eq!(
+        tipo_erro_programa("len(10)"),
+        Some(TipoErroExecucao::ArgumentoInvalido {
+            funcao: "len".to_string(),
+            esperado: "string",
+            recebido: "int"
+        })
+    );
+}
+
+#[test]
+fn test_interpretador_embutido() {
+    let mut interpretador = Interpreter::new();
+    interpretador.define_global("limite", 10);
+    interpretador.register_fn("dobro", |x: i64| x * 2);
+    interpretador.register_fn("saudacao", |nome: String| format!("olá, {}", nome));
+    interpretador.register_fn("raiz", |x: f64| {
+        if x < 0.0 { Err("raiz de número negativo".to_string()) } else { Ok(x.sqrt()) }
+    });
+
+    // O estado é mantido entre as execuções
+    assert_eq!(interpretador.run("var total = dobro(limite)"), Ok(Value::Nil));
+    assert_eq!(interpretador.run("total + 1"), Ok(Value::Inteiro(21)));
+    assert_eq!(interpretador.get_global("total"), Some(Value::Inteiro(20)));
+    assert_eq!(interpretador.get_global("inexistente"), None);
+    assert_eq!(interpretador.run("saudacao(\"Ana\")"), Ok(Value::String("olá, Ana".to_string())));
+    assert_eq!(interpretador.run("raiz(16)"), Ok(Value::Float(4.0)));
+
+    let erro_de = |resultado: Result<Value, InterpreterError>| match resultado {
+        Err(InterpreterError::Execucao(erro)) => erro.tipo,
+        outro => panic!("esperado erro de execução, obtido {:?}", outro),
+    };
+    assert_eq!(
+        erro_de(interpretador.run("dobro(1.5)")),
+        TipoErroExecucao::ArgumentoInvalido { funcao: "dobro".to_string(), esperado: "int", recebido: "float" }
+    );
+    assert_eq!(
+        erro_de(interpretador.run("dobro()")),
+        TipoErroExecucao::NumeroDeArgumentos { esperado: 1, recebido: 0 }
+    );
+    assert_eq!(
+        erro_de(interpretador.run("raiz(-1)")),
+        TipoErroExecucao::ErroNativo("raiz de número negativo".to_string())
+    );
+
+    assert!(matches!(interpretador.run("var = 1"), Err(InterpreterError::Sintaxe(_))));
+    assert!(matches!(interpretador.run("1 @ 2"), Err(InterpreterError::Lexico(_))));
+    // Depois de um erro, o interpretador continua utilizável
+    assert_eq!(interpretador.run("total"), Ok(Value::Inteiro(20)));
+}
+
+#[test]
+fn test_saida_redirecionavel() {
+    let (saida, depuracao, diagnosticos) = (Captura::new(), Captura::new(), Captura::new());
+    let mut contexto = Contexto::new();
+    contexto.saida = Box::new(saida.clone());
+    contexto.depuracao = Box::new(depuracao.clone());
+    contexto.diagnosticos = Box::new(diagnosticos.clone());
+    contexto.rastreamento = true;
+
+    executar_com_contexto("print 1 + 2\nprint \"oi\"\n1 / 0", &mut contexto);
+    assert_eq!(saida.conteudo(), "3\noi\n");
+    assert!(depuracao.conteudo().contains("Declaração linha 1: print 1 + 2"));
+    assert!(!depuracao.conteudo().contains("oi\n"));
+    assert!(diagnosticos.conteudo().starts_with("erro: divisão por zero\n"));
+
+    // A árvore sintática pode ser escrita em qualquer destino
+    let programa = Analisador::new(analisar("print -x").0).analisar_programa().unwrap();
+    let arvore = Captura::new();
+    programa[0].imprimir(&mut arvore.clone(), 0).unwrap();
+    assert_eq!(arvore.conteudo(), "Print:\n   Negação Aritmética (-):\n      Identificador: x\n");
+
+    let mut interpretador = Interpreter::new();
+    let saida = Captura::new();
+    interpretador.set_output(saida.clone());
+    interpretador.run("var i = 0\nwhile (i < 3) { print i; i = i + 1; }").unwrap();
+    assert_eq!(saida.conteudo(), "0\n1\n2\n");
+}
+
+#[test]
+fn test_modo_silencioso_e_rastreamento() {
+    // Por padrão só a saída do print aparece
+    let (saida, depuracao) = (Captura::new(), Captura::new());
+    let mut contexto = Contexto::new();
+    contexto.saida = Box::new(saida.clone());
+    contexto.depuracao = Box::new(depuracao.clone());
+    assert_eq!(executar_com_contexto("var x = 2\nprint x\nx * 3", &mut contexto), Value::Inteiro(6));
+    assert_eq!(saida.conteudo(), "2\n");
+    assert_eq!(depuracao.conteudo(), "");
+
+    let mut interpretador = Interpreter::new();
+    let rastreamento = Captura::new();
+    interpretador.set_output(Captura::new());
+    interpretador.set_trace_output(rastreamento.clone());
+    interpretador.run("1 + 1").unwrap();
+    assert_eq!(rastreamento.conteudo(), "");
+
+    interpretador.set_trace(true);
+    interpretador.run("1 + 1").unwrap();
+    let texto = rastreamento.conteudo();
+    assert!(texto.starts_with("Símbolos encontrados: [NumeroInteiro(1), Soma, NumeroInteiro(1), Fim]\n"));
+    assert!(texto.contains("Declaração linha 1: 1 + 1\nSoma:\n"));
+    assert!(texto.ends_with("Resultado da expressão: 2\n"));
+}
+
+#[test]
+fn test_linha_de_comando() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mini_lox = |argumentos: &[&str]| {
+        let saida = Command::new(env!("CARGO_BIN_EXE_mini_lox")).args(argumentos).output().unwrap();
+        (saida.status.code(), String::from_utf8_lossy(&saida.stdout).into_owned())
+    };
+
+    assert_eq!(mini_lox(&["-e", "print 1 + 2"]), (Some(0), "3\n".to_string()));
+    assert_eq!(mini_lox(&["run", "leiaesse.crl"]).0, Some(0));
+    assert_eq!(mini_lox(&["-e", "var = 1"]).0, Some(65));
+    assert_eq!(mini_lox(&["-e", "1 @ 2"]).0, Some(65));
+    assert_eq!(mini_lox(&["-e", "print 1\n1 / 0\nprint 2"]), (Some(70), "1\n".to_string()));
+    assert_eq!(mini_lox(&["run", "nao_existe.crl"]).0, Some(66));
+    assert_eq!(mini_lox(&["--trace"]).0, Some(64));
+    assert_eq!(mini_lox(&["--tokens", "-e", "x"]), (Some(0), "1:1\tidentificador 'x'\n1:2\tfim da entrada\n".to_string()));
+    assert_eq!(mini_lox(&["--ast", "-e", "print x"]), (Some(0), "Print:\n   Identificador: x\n".to_string()));
+
+    let mut processo = Command::new(env!("CARGO_BIN_EXE_mini_lox"))
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    processo.stdin.take().unwrap().write_all(b"print \"stdin\"").unwrap();
+    let saida = processo.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&saida.stdout), "stdin\n");
+}
+
+#[test]
+fn test_repl() {
+    let (saida, diagnosticos) = (Captura::new(), Captura::new());
+    let mut repl = Repl::new(saida.clone(), diagnosticos.clone());
+    let entrada = "var x = 20
+x + 1
+fun dobro(n) {
+    return n * 2
+}
+dobro(x)
+print \"oi\"
+:env
+:ast -x
+:reset
+x
+:quit
+1 + 1
+";
+    repl.executar(entrada.as_bytes()).unwrap();
+    assert_eq!(
+        saida.conteudo(),
+        "> > 21\n> ... ... > 40\n> oi\n> dobro = <fn dobro>\nx = 20\n> Negação Aritmética (-):\n   Identificador: x\n> > > "
+    );
+    assert!(diagnosticos.conteudo().starts_with("erro: variável 'x' não foi definida\n"));
+
+    // Erros não encerram a sessão
+    let saida = Captura::new();
+    let mut repl = Repl::new(saida.clone(), Captura::new());
+    repl.executar("1 / 0\nvar = 2\n3".as_bytes()).unwrap();
+    assert_eq!(saida.conteudo(), "> > > 3\n> \n");
+}
+
+#[test]
+fn test_literais_booleanos_e_nil() {
+    let simbolos: Vec<_> = analisar("true false nil").0.into_iter().map(|token| token.simbolo).collect();
+    assert_eq!(simbolos, vec![Simbolo::True, Simbolo::False, Simbolo::Nil, Simbolo::Fim]);
+
+    assert_eq!(executar("nil"), Value::Nil);
+    assert_eq!(executar("false or true"), Value::Booleano(true));
+    assert_eq!(executar("var c\nc == nil"), Value::Booleano(true));
+    assert_eq!(executar("fun f() { return nil; }\nf() == nil"), Value::Booleano(true));
+    assert_eq!(executar("type(nil)"), Value::String("nil".to_string()));
+
+    // Palavras reservadas não podem ser usadas como nomes
+    for codigo in ["var true = 1", "true = 1", "fun nil() {}", "var x = nil\nnil = 2"] {
+        assert!(Analisador::new(analisar(codigo).0).analisar_programa().is_err(), "{}", codigo);
+    }
+
+    let saida = Captura::new();
+    let mut interpretador = Interpreter::new();
+    interpretador.set_output(saida.clone());
+    interpretador.run("print true\nprint nil\nprint !nil").unwrap();
+    assert_eq!(saida.conteudo(), "true\nnil\ntrue\n");
+}
+
+#[test]
+fn test_strings() {
+    assert_eq!(executar("\"Olá, \" + \"mundo\""), Value::String("Olá, mundo".to_string()));
+    assert_eq!(executar("var s = \"a\"\ns = s + s + s\nlen(s)"), Value::Inteiro(3));
+    assert_eq!(executar("\"abc\" == \"ab\" + \"c\""), Value::Booleano(true));
+    assert_eq!(executar("\"abc\" != \"abd\""), Value::Booleano(true));
+    assert_eq!(executar("\"1\" == 1"), Value::Booleano(false));
+    assert_eq!(executar("\"abc\" < \"abd\""), Value::Booleano(true));
+    assert_eq!(executar("\"b\" > \"abc\""), Value::Booleano(true));
+    assert_eq!(executar("\"a\" <= \"a\""), Value::Booleano(true));
+
+    assert_eq!(
+        tipo_erro("\"a\" + 1"),
+        Some(TipoErroExecucao::OperandosInvalidos { operador: "+", esquerda: "string", direita: "int" })
+    );
+    assert_eq!(
+        tipo_erro("\"a\" < 1"),
+        Some(TipoErroExecucao::OperandosInvalidos { operador: "<", esquerda: "string", direita: "int" })
+    );
+
+    // O print mostra o conteúdo da string, com os escapes já decodificados
+    let saida = Captura::new();
+    let mut interpretador = Interpreter::new();
+    interpretador.set_output(saida.clone());
+    interpretador.run(r#"print "linha 1\n\t\"linha 2\"""#).unwrap();
+    interpretador.run(r#"print "a" + "\\" + "b""#).unwrap();
+    assert_eq!(saida.conteudo(), "linha 1\n\t\"linha 2\"\na\\b\n");
+}
+
+#[test]
+fn test_interpolacao() {
+    let codigo = "var nome = \"Ana\"\nvar idade = 30\n\"Olá ${nome}, você tem ${idade + 1} anos\"";
+    assert_eq!(executar(codigo), Value::String("Olá Ana, você tem 31 anos".to_string()));
+    assert_eq!(executar("\"${1.0} ${nil} ${true}\""), Value::String("1.0 nil true".to_string()));
+    assert_eq!(executar("\"${\"x${1 + 1}\"}!\""), Value::String("x2!".to_string()));
+    // `\$` e um '$' sem chave são texto comum
+    assert_eq!(executar("\"\\${a} custa $5\""), Value::String("${a} custa $5".to_string()));
+    assert_eq!(
+        executar("class P {\ninit(x) { this.x = x }\nmostrar() { return \"P(${this.x})\" }\n}\nP(2).mostrar()"),
+        Value::String("P(2)".to_string())
+    );
+
+    // Os símbolos da expressão mantêm a posição no código completo
+    let (simbolos, erros) = analisar("\"a\n${b}\"");
+    assert!(erros.is_empty());
+    let Simbolo::StringInterpolada(partes) = &simbolos[0].simbolo else {
+        panic!("esperada string interpolada, encontrado {:?}", simbolos[0].simbolo);
+    };
+    assert_eq!(partes[0], ParteString::Texto("a\n".to_string()));
+    let ParteString::Codigo(codigo) = &partes[1] else { panic!("esperado código") };
+    assert_eq!(codigo[0].span, Span { inicio: 5, linha: 2, coluna: 3, tamanho: 1 });
+
+    let (_, erros) = analisar("\"a ${b\"");
+    assert_eq!(erros[0].tipo, TipoLexError::InterpolacaoNaoTerminada);
+    assert_eq!(erros[0].span.coluna, 4);
+
+    let erros = Analisador::new(analisar("\"${1 2}\"").0).analisar_programa().unwrap_err();
+    assert_eq!(erros[0].esperado, vec!["'}'".to_string()]);
+    assert_eq!(erros[0].span, Span { inicio: 5, linha: 1, coluna: 6, tamanho: 1 });
+    assert_eq!(tipo_erro("\"${x}\""), Some(TipoErroExecucao::VariavelIndefinida("x".to_string())));
+}
+
+#[test]
+fn test_strings_multilinha_e_brutas() {
+    // A primeira e a última linha em branco somem, assim como a indentação comum
+    let codigo = "var nome = \"Ana\"\nvar t = \"\"\"\n    Olá ${nome},\n      tudo \"bem\"?\\n\n    \"\"\"\nt";
+    assert_eq!(executar(codigo), Value::String("Olá Ana,\n  tudo \"bem\"?\n".to_string()));
+    assert_eq!(executar("\"\"\"uma linha\"\"\""), Value::String("uma linha".to_string()));
+    // A indentação da linha das aspas de fechamento também conta
+    assert_eq!(executar("\"\"\"\n  a\n \"\"\""), Value::String(" a".to_string()));
+    assert_eq!(executar("\"\" + \"x\""), Value::String("x".to_string()));
+
+    // Strings brutas não processam escapes nem interpolações
+    assert_eq!(executar(r#"r"\d+\.\d+ ${x}""#), Value::String(r"\d+\.\d+ ${x}".to_string()));
+    assert_eq!(executar("r\"\"\"\n  a\\n\n  b\"\"\""), Value::String("a\\n\nb".to_string()));
+    assert_eq!(executar("var r = 2\nr"), Value::Inteiro(2));
+
+    for codigo in ["\"abc", "r\"abc", "\"\"\"abc\"\"", "\"\"\"\n${1}\n"] {
+        let (_, erros) = analisar(codigo);
+        assert_eq!(erros.len(), 1, "{}", codigo);
+        assert_eq!(erros[0].tipo, TipoLexError::StringNaoTerminada);
+        assert_eq!(erros[0].span.inicio, 0);
+    }
+
+    // No modo interativo, a entrada continua até a string ser fechada
+    let saida = Captura::new();
+    let mut repl = Repl::new(saida.clone(), Captura::new());
+    repl.executar("\"\"\"\n  a\n  \"\"\"".as_bytes()).unwrap();
+    assert_eq!(saida.conteudo(), "> ... ... a\n> \n");
+}
+
+#[test]
+fn test_literais_numericos() {
+    assert_eq!(executar("0xFF"), Value::Inteiro(255));
+    assert_eq!(executar("0b1010"), Value::Inteiro(10));
+    assert_eq!(executar("0o17"), Value::Inteiro(15));
+    assert_eq!(executar("1_000_000"), Value::Inteiro(1_000_000));
+    assert_eq!(executar("0x7FFF_FFFF_FFFF_FFFF"), Value::Inteiro(i64::MAX));
+    assert_eq!(executar("1e10"), Value::Float(1e10));
+    assert_eq!(executar("1.5e-3"), Value::Float(1.5e-3));
+    assert_eq!(executar("2E+2"), Value::Float(200.0));
+    assert_eq!(executar("1.25_5"), Value::Float(1.255));
+
+    let invalido = |codigo: &str| {
+        let (_, erros) = analisar(codigo);
+        assert_eq!(erros.len(), 1, "{}", codigo);
+        match &erros[0].tipo {
+            TipoLexError::NumeroInvalido { numero, motivo } => (numero.clone(), *motivo),
+            outro => panic!("esperado número inválido em {}, encontrado {:?}", codigo, outro),
+        }
+    };
+    assert_eq!(invalido("3."), ("3.".to_string(), "faltam dígitos depois do '.'"));
+    assert_eq!(invalido("1 + .5"), (".5".to_string(), "faltam dígitos antes do '.'"));
+    assert_eq!(invalido("0x"), ("0x".to_string(), "faltam dígitos depois do prefixo"));
+    assert_eq!(invalido("0b102"), ("0b102".to_string(), "dígito binário inválido"));
+    assert_eq!(invalido("0o8"), ("0o8".to_string(), "dígito octal inválido"));
+    assert_eq!(invalido("0xFG"), ("0xFG".to_string(), "dígito hexadecimal inválido"));
+    assert_eq!(invalido("1__0.5"), ("1__0.5".to_string(), "'_' só pode aparecer entre dígitos"));
+    assert_eq!(invalido("1_"), ("1_".to_string(), "'_' só pode aparecer entre dígitos"));
+    assert_eq!(invalido("1e+"), ("1e+".to_string(), "faltam dígitos no expoente"));
+    assert_eq!(invalido("12abc"), ("12abc".to_string(), "caractere inválido no número"));
+
+    // 2^63 só cabe em 64 bits com o menos unário
+    assert_eq!(executar("-9223372036854775808"), Value::Inteiro(i64::MIN));
+    assert_eq!(executar("-0x8000_0000_0000_0000"), Value::Inteiro(i64::MIN));
+    assert_eq!(executar("-9223372036854775808 + 1"), Value::Inteiro(i64::MIN + 1));
+    let erros = Analisador::new(analisar("1 + 9223372036854775808").0).analisar_programa().unwrap_err();
+    assert_eq!(erros[0].to_string(), "esperado inteiro de até 64 bits, encontrado número 9223372036854775808");
+    assert_eq!(erros[0].span, Span { inicio: 4, linha: 1, coluna: 5, tamanho: 19 });
+    let (_, erros) = analisar("-9223372036854775809");
+    assert_eq!(erros[0].tipo, TipoLexError::InteiroMuitoGrande("9223372036854775809".to_string()));
+
+    let (_, erros) = analisar("x = 0x1_0000_0000_0000_0000");
+    assert_eq!(erros[0].tipo, TipoLexError::InteiroMuitoGrande("0x1_0000_0000_0000_0000".to_string()));
+    assert_eq!(erros[0].span, Span { inicio: 4, linha: 1, coluna: 5, tamanho: 23 });
+}
+
+#[test]
+fn test_memoria_liberada() {
+    // Funções guardadas no ambiente que capturam e instâncias que guardam a
+    // si mesmas formam ciclos de Rc; ao descartar ou reiniciar o contexto,
+    // esses objetos precisam ser liberados
+    let codigo = "fun f() { return 1 }
+var g = (x) => x
+fun contador() { var n = 0; fun inc() { n = n + 1; return n } return inc }
+var c = contador()
+class A { m() { return this } }
+var a = A()
+a.m = a.m
+var eu = A()
+eu.eu = eu
+var x = A()
+var y = A()
+x.prox = y
+y.ant = x";
+    let referencias = |contexto: &Contexto| {
+        let Some(Value::Funcao(inc)) = contexto.globais.borrow().obter("c") else { panic!("c não é função") };
+        let instancias = ["a", "eu", "x", "y"].map(|nome| match contexto.globais.borrow().obter(nome) {
+            Some(Value::Instancia(instancia)) => Rc::downgrade(&instancia),
+            _ => panic!("{} não é instância", nome),
+        });
+        (Rc::downgrade(&contexto.globais), Rc::downgrade(&inc.closure), instancias)
+    };
+
+    let mut contexto = Contexto::new();
+    executar_com_contexto(codigo, &mut contexto);
+    let (globais, local, instancias) = referencias(&contexto);
+    drop(contexto);
+    assert!(globais.upgrade().is_none());
+    assert!(local.upgrade().is_none());
+    assert!(instancias.iter().all(|instancia| instancia.upgrade().is_none()));
+
+    let mut contexto = Contexto::new();
+    executar_com_contexto(codigo, &mut contexto);
+    let (globais, local, instancias) = referencias(&contexto);
+    contexto.reiniciar();
+    assert!(globais.upgrade().is_none());
+    assert!(local.upgrade().is_none());
+    assert!(instancias.iter().all(|instancia| instancia.upgrade().is_none()));
+
+    // Com o contexto ainda vivo, os ciclos que o programa não alcança mais
+    // também são liberados, enquanto closures ainda em uso continuam intactas
+    let mut contexto = Contexto::new();
+    let definicoes = "fun mk() { var big = \"abc\"; fun inner() { return big } return inner }
+var c = contador()
+c()";
+    executar_com_contexto(codigo, &mut contexto);
+    executar_com_contexto(definicoes, &mut contexto);
+    let descartada = match executar_com_contexto("mk()", &mut contexto) {
+        Value::Funcao(funcao) => Rc::downgrade(&funcao.closure),
+        _ => panic!("mk() não é função"),
+    };
+    let Value::Funcao(guardada) = executar_com_contexto("mk()", &mut contexto) else { panic!("mk() não é função") };
+    assert!(descartada.upgrade().is_some());
+    executar_com_contexto("var i = 0\nwhile (i < 5000) { mk(); i = i + 1; }", &mut contexto);
+    assert!(descartada.upgrade().is_none());
+    assert_eq!(guardada.closure.borrow().obter("big"), Some(Value::from("abc")));
+    assert_eq!(executar_com_contexto("c()", &mut contexto), Value::Inteiro(2));
+
+    let descartada = match executar_com_contexto("var z = A()\nz.eu = z\nz", &mut contexto) {
+        Value::Instancia(instancia) => Rc::downgrade(&instancia),
+        _ => panic!("z não é instância"),
+    };
+    executar_com_contexto("z = nil\nvar i = 0\nwhile (i < 5000) { var p = A(); p.eu = p; i = i + 1; }", &mut contexto);
+    assert!(descartada.upgrade().is_none());
+    assert_eq!(executar_com_contexto("eu.eu == eu and x.prox.ant == x", &mut contexto), Value::Booleano(true));
+}